@@ -7,7 +7,7 @@ use core::convert::AsRef;
 use core::convert::AsMut;
 
 use core::slice;
-use core::mem;
+use core::marker::PhantomData;
 
 use common::Word;
 
@@ -42,6 +42,9 @@ impl<T> Pointer<T>
 where
     T: Sized,
 {
+    /// # Safety
+    ///
+    /// The `raw` must be a valid non null pointer
     pub unsafe fn from_raw(raw: *mut T) -> Self {
         Pointer {
             raw: NonNull::new_unchecked(raw),
@@ -66,8 +69,7 @@ where
     type Output = T;
 
     fn index(&self, index: Word) -> &Self::Output {
-        let index = if index < self.length { index } else { 0 };
-        unsafe { &*self.raw.as_ptr().offset(index as _) }
+        &self.as_slice()[index]
     }
 }
 
@@ -76,8 +78,7 @@ where
     T: Sized,
 {
     fn index_mut(&mut self, index: Word) -> &mut Self::Output {
-        let index = if index < self.length { index } else { 0 };
-        unsafe { &mut *self.raw.as_ptr().offset(index as _) }
+        &mut self.as_mut_slice()[index]
     }
 }
 
@@ -85,9 +86,12 @@ impl<T> Array<T>
 where
     T: Sized,
 {
+    /// # Safety
+    ///
+    /// The `raw` must point to `length` valid elements
     pub unsafe fn from_raw(raw: *mut T, length: Word) -> Self {
         Array {
-            length: length,
+            length,
             raw: NonNull::new_unchecked(raw),
         }
     }
@@ -97,11 +101,51 @@ where
     }
 
     pub fn as_slice(&self) -> &[T] {
-        unsafe { slice::from_raw_parts(self.raw.as_ref(), self.length * mem::size_of::<T>()) }
+        unsafe { slice::from_raw_parts(self.raw.as_ref(), self.length) }
     }
 
     pub fn as_mut_slice(&mut self) -> &mut [T] {
-        unsafe { slice::from_raw_parts_mut(self.raw.as_mut(), self.length * mem::size_of::<T>()) }
+        unsafe { slice::from_raw_parts_mut(self.raw.as_mut(), self.length) }
+    }
+
+    pub fn get(&self, index: Word) -> Option<&T> {
+        self.as_slice().get(index)
+    }
+
+    pub fn get_mut(&mut self, index: Word) -> Option<&mut T> {
+        self.as_mut_slice().get_mut(index)
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        self.as_mut_slice().iter_mut()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Array<T>
+where
+    T: Sized,
+{
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Array<T>
+where
+    T: Sized,
+{
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
     }
 }
 
@@ -123,8 +167,10 @@ where
     type Output = T;
 
     fn index(&self, index: Word) -> &Self::Output {
-        let index = if index < self.length { index } else { 0 };
-        unsafe { &*((self.raw.as_ptr() as *mut u8).offset((index * self.stride) as _) as *mut T) }
+        match self.get(index) {
+            Some(element) => element,
+            None => out_of_bounds(index, self.length),
+        }
     }
 }
 
@@ -133,8 +179,11 @@ where
     T: Sized,
 {
     fn index_mut(&mut self, index: Word) -> &mut Self::Output {
-        let index = if index < self.length { index } else { 0 };
-        unsafe { &mut *((self.raw.as_ptr() as *mut u8).offset((index * self.stride) as _) as *mut T) }
+        let length = self.length;
+        match self.get_mut(index) {
+            Some(element) => element,
+            None => out_of_bounds(index, length),
+        }
     }
 }
 
@@ -142,10 +191,13 @@ impl<T> ArrayStride<T>
 where
     T: Sized,
 {
+    /// # Safety
+    ///
+    /// The `raw` must point to `length` valid elements placed `stride` bytes apart
     pub unsafe fn from_raw(raw: *mut T, length: Word, stride: Word) -> Self {
         ArrayStride {
-            length: length,
-            stride: stride,
+            length,
+            stride,
             raw: NonNull::new_unchecked(raw),
         }
     }
@@ -165,4 +217,179 @@ where
     pub fn as_mut_ptr(&self) -> *mut T {
         self.raw.as_ptr()
     }
+
+    // the caller checks the bounds
+    unsafe fn element(&self, index: Word) -> *mut T {
+        (self.raw.as_ptr() as *mut u8).add(index * self.stride) as *mut T
+    }
+
+    pub fn get(&self, index: Word) -> Option<&T> {
+        if index < self.length {
+            Some(unsafe { &*self.element(index) })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, index: Word) -> Option<&mut T> {
+        if index < self.length {
+            Some(unsafe { &mut *self.element(index) })
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> StrideIter<'_, T> {
+        StrideIter {
+            array: self,
+            front: 0,
+            back: self.length,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> StrideIterMut<'_, T> {
+        StrideIterMut {
+            raw: self.raw,
+            stride: self.stride,
+            front: 0,
+            back: self.length,
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[cold]
+fn out_of_bounds(index: Word, length: Word) -> ! {
+    panic!(
+        "index out of bounds: the length is {} but the index is {}",
+        length, index
+    )
+}
+
+impl<'a, T> IntoIterator for &'a ArrayStride<T>
+where
+    T: Sized,
+{
+    type Item = &'a T;
+    type IntoIter = StrideIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut ArrayStride<T>
+where
+    T: Sized,
+{
+    type Item = &'a mut T;
+    type IntoIter = StrideIterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
 }
+
+pub struct StrideIter<'a, T>
+where
+    T: Sized + 'a,
+{
+    array: &'a ArrayStride<T>,
+    front: Word,
+    back: Word,
+}
+
+impl<'a, T> Iterator for StrideIter<'a, T>
+where
+    T: Sized,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front < self.back {
+            self.front += 1;
+            Some(unsafe { &*self.array.element(self.front - 1) })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let length = self.back - self.front;
+        (length, Some(length))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for StrideIter<'a, T>
+where
+    T: Sized,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front < self.back {
+            self.back -= 1;
+            Some(unsafe { &*self.array.element(self.back) })
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for StrideIter<'a, T> where T: Sized {}
+
+pub struct StrideIterMut<'a, T>
+where
+    T: Sized + 'a,
+{
+    raw: NonNull<T>,
+    stride: Word,
+    front: Word,
+    back: Word,
+    phantom: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> StrideIterMut<'a, T>
+where
+    T: Sized,
+{
+    // every index is yielded only once, so the references do not alias
+    unsafe fn element(&self, index: Word) -> &'a mut T {
+        &mut *((self.raw.as_ptr() as *mut u8).add(index * self.stride) as *mut T)
+    }
+}
+
+impl<'a, T> Iterator for StrideIterMut<'a, T>
+where
+    T: Sized,
+{
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front < self.back {
+            self.front += 1;
+            Some(unsafe { self.element(self.front - 1) })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let length = self.back - self.front;
+        (length, Some(length))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for StrideIterMut<'a, T>
+where
+    T: Sized,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front < self.back {
+            self.back -= 1;
+            Some(unsafe { self.element(self.back) })
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for StrideIterMut<'a, T> where T: Sized {}
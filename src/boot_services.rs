@@ -2,101 +2,221 @@ use common::Header;
 use common::Word;
 use common::Status;
 use common::Handle;
+use common::Event;
 use common::Guid;
 use common::Registration;
 use common::Address;
-use common::PAGE_SIZE;
 use common::HasGuid;
+use common::Protocol;
+use common::Bool;
+use common::Char16;
 
 use array::ArrayStride;
 
+use pool::PoolSlice;
+
+use interface::device_path::DevicePath;
+use interface::device_path::node::Ucs2;
+
 use core::ptr;
-use core::slice;
+use core::mem;
+use core::ops::Deref;
+use core::marker::PhantomData;
+
+use alloc::vec::Vec;
+use alloc::string::String;
 
 #[repr(C)]
 pub struct BootServices {
     header: Header,
 
-    raise_tpl: extern "win64" fn(/* in */ new_tpl: Tpl) -> Tpl,
-    restore_tpl: extern "win64" fn(/* in */ old_tpl: Tpl) -> (),
+    raise_tpl: extern "efiapi" fn(/* in */ new_tpl: Tpl) -> Tpl,
+    restore_tpl: extern "efiapi" fn(/* in */ old_tpl: Tpl) -> (),
 
-    allocate_pages: extern "win64" fn(
-        /* in */ allocate_type: Word,
-        /* in */ memory_type: Word,
+    allocate_pages: extern "efiapi" fn(
+        /* in */ allocate_type: u32,
+        /* in */ memory_type: u32,
         /* in */ pages: Word,
         /* in out */ physical_address: *mut Address,
     ) -> Status,
-    free_pages:
-        extern "win64" fn(/* in */ physical_address: Address, /* in */ pages: Word) -> Status,
-    get_memory_map: extern "win64" fn(
+    free_pages: extern "efiapi" fn(
+        /* in */ physical_address: Address,
+        /* in */ pages: Word,
+    ) -> Status,
+    get_memory_map: extern "efiapi" fn(
         /* in out */ memory_map_size: *mut Word,
         /* in out */ memory_map: *mut MemoryDescriptor,
         /* out */ map_key: *mut Word,
         /* out */ descriptor_size: *mut Word,
         /* out */ descriptor_version: *mut u32,
     ) -> Status,
-    allocate_pool: extern "win64" fn() -> (),
-    free_pool: extern "win64" fn() -> (),
-
-    create_event: extern "win64" fn() -> (),
-    set_timer: extern "win64" fn() -> (),
-    wait_for_event: extern "win64" fn() -> (),
-    signal_event: extern "win64" fn() -> (),
-    close_event: extern "win64" fn() -> (),
-    check_event: extern "win64" fn() -> (),
-
-    install_protocol_interface: extern "win64" fn() -> (),
-    reinstall_protocol_interface: extern "win64" fn() -> (),
-    uninstall_protocol_interface: extern "win64" fn() -> (),
-    handle_protocol: extern "win64" fn(
+    allocate_pool: extern "efiapi" fn(
+        /* in */ pool_type: u32,
+        /* in */ size: Word,
+        /* out */ buffer: *mut *mut u8,
+    ) -> Status,
+    free_pool: extern "efiapi" fn(/* in */ buffer: *mut u8) -> Status,
+
+    create_event: extern "efiapi" fn(
+        /* in */ event_type: u32,
+        /* in */ notify_tpl: Tpl,
+        /* in */ notify_function: Option<EventNotify>,
+        /* in */ notify_context: *mut (),
+        /* out */ event: *mut Event,
+    ) -> Status,
+    set_timer: extern "efiapi" fn() -> (),
+    wait_for_event: extern "efiapi" fn(
+        /* in */ number_of_events: Word,
+        /* in */ event: *const Event,
+        /* out */ index: *mut Word,
+    ) -> Status,
+    signal_event: extern "efiapi" fn(/* in */ event: Event) -> Status,
+    close_event: extern "efiapi" fn(/* in */ event: Event) -> Status,
+    check_event: extern "efiapi" fn(/* in */ event: Event) -> Status,
+
+    install_protocol_interface: extern "efiapi" fn(
+        /* in out */ handle: *mut Handle,
+        /* in */ protocol: *const Guid,
+        /* in */ interface_type: u32,
+        /* in */ interface: *mut (),
+    ) -> Status,
+    reinstall_protocol_interface: extern "efiapi" fn(
+        /* in */ handle: Handle,
+        /* in */ protocol: *const Guid,
+        /* in */ old_interface: *mut (),
+        /* in */ new_interface: *mut (),
+    ) -> Status,
+    uninstall_protocol_interface: extern "efiapi" fn(
+        /* in */ handle: Handle,
+        /* in */ protocol: *const Guid,
+        /* in */ interface: *mut (),
+    ) -> Status,
+    handle_protocol: extern "efiapi" fn(
         /* in */ handle: Handle,
         /* in */ protocol: *const Guid,
         /* out */ interface: *mut *const (),
     ) -> Status,
-    pchandle_protocol: extern "win64" fn() -> (),
-    register_protocol_notify: extern "win64" fn() -> (),
-    locate_handle: extern "win64" fn() -> (),
-    locate_device_path: extern "win64" fn() -> (),
-    install_configuration_table: extern "win64" fn() -> (),
-
-    load_image: extern "win64" fn() -> (),
-    start_image: extern "win64" fn() -> (),
-    exit: extern "win64" fn() -> (),
-    unload_image: extern "win64" fn() -> (),
+    pchandle_protocol: extern "efiapi" fn() -> (),
+    register_protocol_notify: extern "efiapi" fn(
+        /* in */ protocol: *const Guid,
+        /* in */ event: Event,
+        /* out */ registration: *mut Registration,
+    ) -> Status,
+    locate_handle: extern "efiapi" fn(
+        /* in */ search_type: u32,
+        /* in */ protocol: *const Guid,
+        /* in */ search_key: Registration,
+        /* in out */ buffer_size: *mut Word,
+        /* out */ buffer: *mut Handle,
+    ) -> Status,
+    locate_device_path: extern "efiapi" fn(
+        /* in */ protocol: *const Guid,
+        /* in out */ device_path: *mut *const u8,
+        /* out */ device: *mut Handle,
+    ) -> Status,
+    install_configuration_table: extern "efiapi" fn() -> (),
+
+    load_image: extern "efiapi" fn(
+        /* in */ boot_policy: Bool,
+        /* in */ parent_image_handle: Handle,
+        /* in */ device_path: *const u8,
+        /* in */ source_buffer: *const u8,
+        /* in */ source_size: Word,
+        /* out */ image_handle: *mut Handle,
+    ) -> Status,
+    start_image: extern "efiapi" fn(
+        /* in */ image_handle: Handle,
+        /* out */ exit_data_size: *mut Word,
+        /* out */ exit_data: *mut *mut Char16,
+    ) -> Status,
+    exit: extern "efiapi" fn(
+        /* in */ image_handle: Handle,
+        /* in */ exit_status: Status,
+        /* in */ exit_data_size: Word,
+        /* in */ exit_data: *mut Char16,
+    ) -> Status,
+    unload_image: extern "efiapi" fn(/* in */ image_handle: Handle) -> Status,
     exit_boot_services:
-        extern "win64" fn(/* in */ handle: Handle, /* in */ map_key: Word) -> Status,
+        extern "efiapi" fn(/* in */ handle: Handle, /* in */ map_key: Word) -> Status,
 
-    get_next_monotonic_count: extern "win64" fn() -> (),
-    stall: extern "win64" fn() -> (),
-    set_watchdog_timer: extern "win64" fn() -> (),
+    get_next_monotonic_count: extern "efiapi" fn() -> (),
+    stall: extern "efiapi" fn() -> (),
+    set_watchdog_timer: extern "efiapi" fn() -> (),
 
-    connect_controller: extern "win64" fn() -> (),
-    disconnect_controller: extern "win64" fn() -> (),
+    connect_controller: extern "efiapi" fn() -> (),
+    disconnect_controller: extern "efiapi" fn() -> (),
 
-    open_protocol: extern "win64" fn() -> (),
-    close_protocol: extern "win64" fn() -> (),
-    open_protocol_information: extern "win64" fn() -> (),
+    open_protocol: extern "efiapi" fn(
+        /* in */ handle: Handle,
+        /* in */ protocol: *const Guid,
+        /* out */ interface: *mut *mut (),
+        /* in */ agent_handle: Handle,
+        /* in */ controller_handle: Handle,
+        /* in */ attributes: u32,
+    ) -> Status,
+    close_protocol: extern "efiapi" fn(
+        /* in */ handle: Handle,
+        /* in */ protocol: *const Guid,
+        /* in */ agent_handle: Handle,
+        /* in */ controller_handle: Handle,
+    ) -> Status,
+    open_protocol_information: extern "efiapi" fn(
+        /* in */ handle: Handle,
+        /* in */ protocol: *const Guid,
+        /* out */ entry_buffer: *mut *mut OpenProtocolInfoEntry,
+        /* out */ entry_count: *mut Word,
+    ) -> Status,
 
-    protocols_per_handle: extern "win64" fn() -> (),
-    locate_handle_buffer: extern "win64" fn(
+    protocols_per_handle: extern "efiapi" fn(
+        /* in */ handle: Handle,
+        /* out */ protocol_buffer: *mut *mut *const Guid,
+        /* out */ protocol_buffer_count: *mut Word,
+    ) -> Status,
+    locate_handle_buffer: extern "efiapi" fn(
         /* in */ search_type: u32,
         /* in */ protocol: *const Guid,
         /* in */ search_key: Registration,
         /* in out */ no_handles: *mut Word,
-        /* in out */ buffer: *mut *const Handle,
+        /* out */ buffer: *mut *mut Handle,
+    ) -> Status,
+    locate_protocol: extern "efiapi" fn(
+        /* in */ protocol: *const Guid,
+        /* in */ registration: Registration,
+        /* out */ interface: *mut *const (),
     ) -> Status,
-    locate_protocol: extern "win64" fn() -> (),
-    install_multiple_protocol_interfaces: extern "win64" fn() -> (),
-    uninstall_multiple_protocol_interfaces: extern "win64" fn() -> (),
+    // pairs of the guid and the interface terminated by null
+    install_multiple_protocol_interfaces:
+        extern "efiapi" fn(/* in out */ handle: *mut Handle, ...) -> Status,
+    uninstall_multiple_protocol_interfaces:
+        extern "efiapi" fn(/* in */ handle: Handle, ...) -> Status,
 
-    calculate_crc32: extern "win64" fn() -> (),
+    calculate_crc32: extern "efiapi" fn() -> (),
 
-    copy_mem: extern "win64" fn() -> (),
-    set_mem: extern "win64" fn() -> (),
-    create_event_ex: extern "win64" fn() -> (),
+    copy_mem: extern "efiapi" fn() -> (),
+    set_mem: extern "efiapi" fn() -> (),
+    create_event_ex: extern "efiapi" fn() -> (),
 }
 
-type Tpl = Word;
+pub type Tpl = Word;
+
+pub const TPL_APPLICATION: Tpl = 4;
+pub const TPL_CALLBACK: Tpl = 8;
+pub const TPL_NOTIFY: Tpl = 16;
+pub const TPL_HIGH_LEVEL: Tpl = 31;
+
+pub type EventNotify =
+    extern "efiapi" fn(/* in */ event: Event, /* in */ context: *mut ());
+
+bitflags! {
+    pub struct EventType: u32 {
+        const TIMER                         = 0x80000000;
+        const RUNTIME                       = 0x40000000;
+        const NOTIFY_WAIT                   = 0x00000100;
+        const NOTIFY_SIGNAL                 = 0x00000200;
+        const SIGNAL_EXIT_BOOT_SERVICES     = 0x00000201;
+        const SIGNAL_VIRTUAL_ADDRESS_CHANGE = 0x60000202;
+    }
+}
 
 #[repr(C)]
 #[derive(Copy, Clone)]
@@ -126,10 +246,22 @@ bitflags! {
     }
 }
 
-pub struct MemoryDescriptorArray {
+/// The map is allocated from the pool and freed on drop,
+/// use `leak` to keep it after `exit_boot_services`
+pub struct MemoryDescriptorArray<'a> {
     pub array: ArrayStride<MemoryDescriptor>,
     pub key: Word,
     pub descriptor_version: u32,
+    buffer: PoolSlice<'a, u8>,
+}
+
+impl<'a> MemoryDescriptorArray<'a> {
+    /// The pool cannot be freed once the boot services are exited
+    pub fn leak(self) -> ArrayStride<MemoryDescriptor> {
+        let MemoryDescriptorArray { array, buffer, .. } = self;
+        let _ = buffer.into_raw();
+        array
+    }
 }
 
 pub enum AllocateType {
@@ -139,7 +271,7 @@ pub enum AllocateType {
 }
 
 impl AllocateType {
-    fn into_raw(self) -> (Word, Address) {
+    fn into_raw(self) -> (u32, Address) {
         match self {
             AllocateType::AllocateAnyPages => (0, Address::NULL),
             AllocateType::AllocateMaxAddress(t) => (1, t),
@@ -168,12 +300,370 @@ pub enum MemoryType {
     PersistentMemory,
 }
 
+impl MemoryType {
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        use self::MemoryType::*;
+
+        let types = [
+            ReservedMemoryType,
+            LoaderCode,
+            LoaderData,
+            BootServicesCode,
+            BootServicesData,
+            RuntimeServicesCode,
+            RuntimeServicesData,
+            ConventionalMemory,
+            UnusableMemory,
+            ACPIReclaimMemory,
+            ACPIMemoryNVS,
+            MemoryMappedIO,
+            MemoryMappedIOPortSpace,
+            PalCode,
+            PersistentMemory,
+        ];
+        types.get(raw as usize).cloned()
+    }
+}
+
 pub enum SearchKey {
     AllHandles,
     ByRegisterNotify(Registration),
     ByProtocol(Guid),
 }
 
+impl SearchKey {
+    fn as_raw(&self) -> (u32, *const Guid, Registration) {
+        match *self {
+            SearchKey::AllHandles => (0, ptr::null(), Registration::NULL),
+            SearchKey::ByRegisterNotify(registration) => (1, ptr::null(), registration),
+            SearchKey::ByProtocol(ref guid) => (2, guid, Registration::NULL),
+        }
+    }
+}
+
+bitflags! {
+    pub struct OpenProtocolAttributes: u32 {
+        const BY_HANDLE_PROTOCOL  = 0x00000001;
+        const GET_PROTOCOL        = 0x00000002;
+        const TEST_PROTOCOL       = 0x00000004;
+        const BY_CHILD_CONTROLLER = 0x00000008;
+        const BY_DRIVER           = 0x00000010;
+        const EXCLUSIVE           = 0x00000020;
+    }
+}
+
+/// Who holds the protocol opened and how
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct OpenProtocolInfoEntry {
+    pub agent_handle: Handle,
+    pub controller_handle: Handle,
+    attributes: u32,
+    pub open_count: u32,
+}
+
+impl OpenProtocolInfoEntry {
+    pub fn attributes(&self) -> OpenProtocolAttributes {
+        OpenProtocolAttributes::from_bits_truncate(self.attributes)
+    }
+}
+
+/// Protocol interface opened by the agent, it is closed on drop
+pub struct ScopedProtocol<'a, T>
+where
+    T: HasGuid + ?Sized,
+{
+    boot_services: &'a BootServices,
+    interface: *mut T,
+    handle: Handle,
+    agent: Handle,
+    controller: Handle,
+    exclusive: bool,
+}
+
+impl<'a, T> ScopedProtocol<'a, T>
+where
+    T: HasGuid + ?Sized,
+{
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Only exclusive opens give the mutable access
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if self.exclusive {
+            Some(unsafe { &mut *self.interface })
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T> Deref for ScopedProtocol<'a, T>
+where
+    T: HasGuid + ?Sized,
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.interface }
+    }
+}
+
+impl<'a, T> Drop for ScopedProtocol<'a, T>
+where
+    T: HasGuid + ?Sized,
+{
+    fn drop(&mut self) {
+        let _ =
+            self.boot_services
+                .close_protocol::<T>(self.handle, self.agent, Some(self.controller));
+    }
+}
+
+/// Yields the handles the protocol was installed on since the last call,
+/// the iteration ends when there are no more such handles, but it may be resumed later,
+/// a failed lookup is yielded as the error, the event is closed on drop and it cancels the registration
+pub struct ProtocolNotify<'a, T>
+where
+    T: HasGuid + ?Sized,
+{
+    boot_services: &'a BootServices,
+    event: Event,
+    registration: Registration,
+    pending: Vec<Handle>,
+    phantom_data: PhantomData<T>,
+}
+
+impl<'a, T> ProtocolNotify<'a, T>
+where
+    T: HasGuid + ?Sized,
+{
+    /// Signaled when the protocol is installed
+    pub fn event(&self) -> Event {
+        self.event
+    }
+
+    pub fn registration(&self) -> Registration {
+        self.registration
+    }
+
+    /// Returns `None` if there are no new handles
+    pub fn next_handle(&mut self) -> Result<Option<Handle>, Status> {
+        if self.pending.is_empty() {
+            let search_key = SearchKey::ByRegisterNotify(self.registration);
+            match self.boot_services.locate_handle_buffer(search_key) {
+                Err(Status::NOT_FOUND) => (),
+                Err(status) => return Err(status),
+                Ok(handles) => self.pending.extend(handles.iter().rev()),
+            }
+        }
+        Ok(self.pending.pop())
+    }
+
+    /// Blocks until the protocol is installed on some handle
+    pub fn wait(&mut self) -> Result<Handle, Status> {
+        loop {
+            if let Some(handle) = self.next_handle()? {
+                return Ok(handle);
+            }
+            self.boot_services.wait_for_event(&[self.event])?;
+        }
+    }
+}
+
+impl<'a, T> Iterator for ProtocolNotify<'a, T>
+where
+    T: HasGuid + ?Sized,
+{
+    type Item = Result<Handle, Status>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_handle().transpose()
+    }
+}
+
+impl<'a, T> Drop for ProtocolNotify<'a, T>
+where
+    T: HasGuid + ?Sized,
+{
+    fn drop(&mut self) {
+        let _ = self.boot_services.close_event(self.event);
+    }
+}
+
+/// The image exited with an error, the data is allocated from the pool by the image,
+/// it is the null terminated message optionally followed by binary data
+pub struct ImageExit<'a> {
+    pub status: Status,
+    pub data: PoolSlice<'a, u8>,
+}
+
+impl<'a> ImageExit<'a> {
+    /// Invalid sequences are replaced with `U+FFFD`
+    pub fn message(&self) -> String {
+        Ucs2::new(&self.data).chars().collect()
+    }
+}
+
+// the only interface type defined by the specification
+const NATIVE_INTERFACE: u32 = 0;
+
+/// Protocol interface installed on the handle, it is uninstalled on drop
+pub struct InstalledInterface<'a, T>
+where
+    T: HasGuid + ?Sized,
+{
+    boot_services: &'a BootServices,
+    handle: Handle,
+    // the firmware and the consumers of the protocol might write through it
+    interface: *mut T,
+}
+
+impl<'a, T> InstalledInterface<'a, T>
+where
+    T: HasGuid + ?Sized,
+{
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Replaces the interface, the drivers managing the handle are reconnected
+    pub fn reinstall(&mut self, interface: &'static mut T) -> Result<(), Status> {
+        let interface = interface as *mut T;
+        self.boot_services.reinstall_protocol_interface_raw(
+            self.handle,
+            self.interface,
+            interface,
+        )?;
+        self.interface = interface;
+        Ok(())
+    }
+
+    /// The firmware refuses it while some driver has the protocol opened
+    pub fn uninstall(self) -> Result<(), Status> {
+        let result = self
+            .boot_services
+            .uninstall_protocol_interface(self.handle, self.interface);
+        mem::forget(self);
+        result
+    }
+
+    /// Keeps the interface installed, it lives forever anyway
+    pub fn leak(self) -> Handle {
+        let handle = self.handle;
+        mem::forget(self);
+        handle
+    }
+}
+
+impl<'a, T> Drop for InstalledInterface<'a, T>
+where
+    T: HasGuid + ?Sized,
+{
+    fn drop(&mut self) {
+        let _ = self
+            .boot_services
+            .uninstall_protocol_interface(self.handle, self.interface);
+    }
+}
+
+/// Tuple of `&'static mut` protocol interfaces, installed and uninstalled at once
+pub trait InterfaceList {
+    #[doc(hidden)]
+    type Raw: Copy;
+    #[doc(hidden)]
+    fn into_raw(self) -> Self::Raw;
+    #[doc(hidden)]
+    fn install(raw: Self::Raw, boot_services: &BootServices, handle: &mut Handle) -> Status;
+    #[doc(hidden)]
+    fn uninstall(raw: Self::Raw, boot_services: &BootServices, handle: Handle) -> Status;
+}
+
+macro_rules! interface_list {
+    ($($t:ident $index:tt),*) => {
+        impl<$($t),*> InterfaceList for ($(&'static mut $t,)*)
+        where
+            $($t: HasGuid + ?Sized,)*
+        {
+            type Raw = ($(*mut $t,)*);
+
+            fn into_raw(self) -> Self::Raw {
+                ($(self.$index as *mut $t,)*)
+            }
+
+            fn install(raw: Self::Raw, boot_services: &BootServices, handle: &mut Handle) -> Status {
+                let install_multiple_protocol_interfaces =
+                    boot_services.install_multiple_protocol_interfaces;
+                install_multiple_protocol_interfaces(
+                    handle,
+                    $(&$t::GUID as *const Guid, raw.$index as *mut (),)*
+                    ptr::null::<Guid>(),
+                )
+            }
+
+            fn uninstall(raw: Self::Raw, boot_services: &BootServices, handle: Handle) -> Status {
+                let uninstall_multiple_protocol_interfaces =
+                    boot_services.uninstall_multiple_protocol_interfaces;
+                uninstall_multiple_protocol_interfaces(
+                    handle,
+                    $(&$t::GUID as *const Guid, raw.$index as *mut (),)*
+                    ptr::null::<Guid>(),
+                )
+            }
+        }
+    };
+}
+
+interface_list!(A 0);
+interface_list!(A 0, B 1);
+interface_list!(A 0, B 1, C 2);
+interface_list!(A 0, B 1, C 2, D 3);
+interface_list!(A 0, B 1, C 2, D 3, E 4);
+interface_list!(A 0, B 1, C 2, D 3, E 4, F 5);
+
+/// Protocol interfaces installed on the handle at once, they are uninstalled on drop
+pub struct InstalledInterfaces<'a, L>
+where
+    L: InterfaceList + 'a,
+{
+    boot_services: &'a BootServices,
+    handle: Handle,
+    interfaces: L::Raw,
+}
+
+impl<'a, L> InstalledInterfaces<'a, L>
+where
+    L: InterfaceList + 'a,
+{
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// The firmware refuses it while some driver has any of the protocols opened
+    pub fn uninstall(self) -> Result<(), Status> {
+        let result = L::uninstall(self.interfaces, self.boot_services, self.handle).check(());
+        mem::forget(self);
+        result
+    }
+
+    /// Keeps the interfaces installed, they live forever anyway
+    pub fn leak(self) -> Handle {
+        let handle = self.handle;
+        mem::forget(self);
+        handle
+    }
+}
+
+impl<'a, L> Drop for InstalledInterfaces<'a, L>
+where
+    L: InterfaceList + 'a,
+{
+    fn drop(&mut self) {
+        let _ = L::uninstall(self.interfaces, self.boot_services, self.handle);
+    }
+}
+
 const SIGNATURE: u64 = 0x56524553544f4f42;
 
 impl BootServices {
@@ -201,72 +691,488 @@ impl BootServices {
         free_pages(address, pages).check(())
     }
 
-    pub fn get_memory_map(&self) -> Result<MemoryDescriptorArray, Status> {
+    pub fn get_memory_map(&self) -> Result<MemoryDescriptorArray<'_>, Status> {
         let get_memory_map = self.get_memory_map;
-        let mut map_size = 0 as _;
-        let mut key = 0 as _;
-        let mut stride = 0 as _;
-        let mut version = 0 as _;
-
-        let _ = get_memory_map(
-            &mut map_size,
-            0 as *mut MemoryDescriptor,
-            &mut key,
-            &mut stride,
-            &mut version,
-        ).check(());
-
-        let length = map_size / stride;
-        assert_eq!(map_size % stride, 0);
-        let pages = map_size / PAGE_SIZE + 1;
-
-        let address = self.allocate_pages(
-            AllocateType::AllocateAnyPages,
-            MemoryType::LoaderData,
-            pages,
-        )?;
-        let array: ArrayStride<MemoryDescriptor> = unsafe { ArrayStride::from_raw(address.cast(), length, stride) };
-
-        get_memory_map(
-            &mut map_size,
-            unsafe { address.cast() },
-            &mut key,
-            &mut stride,
-            &mut version,
-        ).check(MemoryDescriptorArray {
-            array: array,
-            key: key,
-            descriptor_version: version,
-        })
+        let mut buffer: Option<PoolSlice<u8>> = None;
+        let mut key = 0;
+        let mut stride = 0;
+        let mut version = 0;
+
+        loop {
+            let mut map_size = buffer.as_ref().map_or(0, |buffer| buffer.len());
+            let map = buffer
+                .as_mut()
+                .map_or(ptr::null_mut(), |buffer| buffer.as_mut_ptr());
+            let status =
+                get_memory_map(&mut map_size, map as _, &mut key, &mut stride, &mut version);
+            if status == Status::BUFFER_TOO_SMALL {
+                // the allocation itself might split a free region
+                let size = map_size + 2 * stride;
+                let raw = self.allocate_pool(MemoryType::LoaderData, size)?;
+                buffer = Some(unsafe { PoolSlice::from_raw(self, raw, size) });
+                continue;
+            }
+            status.check(())?;
+
+            let buffer = buffer.ok_or(Status::BUFFER_TOO_SMALL)?;
+            let array = unsafe { ArrayStride::from_raw(map as _, map_size / stride, stride) };
+            return Ok(MemoryDescriptorArray {
+                array,
+                key,
+                descriptor_version: version,
+                buffer,
+            });
+        }
+    }
+
+    pub fn allocate_pool(&self, memory_type: MemoryType, size: Word) -> Result<*mut u8, Status> {
+        let allocate_pool = self.allocate_pool;
+        let mut buffer = ptr::null_mut();
+        allocate_pool(memory_type as _, size, &mut buffer).check(buffer)
+    }
+
+    pub fn free_pool(&self, buffer: *mut u8) -> Result<(), Status> {
+        let free_pool = self.free_pool;
+        free_pool(buffer).check(())
+    }
+
+    /// The `notify_function` is required for `NOTIFY_WAIT` and `NOTIFY_SIGNAL` events
+    pub fn create_event(
+        &self,
+        event_type: EventType,
+        notify_tpl: Tpl,
+        notify_function: Option<EventNotify>,
+        notify_context: *mut (),
+    ) -> Result<Event, Status> {
+        let create_event = self.create_event;
+        let mut event = Event::NULL;
+        create_event(
+            event_type.bits(),
+            notify_tpl,
+            notify_function,
+            notify_context,
+            &mut event,
+        ).check(event)
+    }
+
+    /// Returns the index of the signaled event
+    pub fn wait_for_event(&self, events: &[Event]) -> Result<Word, Status> {
+        let wait_for_event = self.wait_for_event;
+        let mut index = 0;
+        wait_for_event(events.len() as _, events.as_ptr(), &mut index).check(index)
+    }
+
+    pub fn signal_event(&self, event: Event) -> Result<(), Status> {
+        let signal_event = self.signal_event;
+        signal_event(event).check(())
+    }
+
+    pub fn close_event(&self, event: Event) -> Result<(), Status> {
+        let close_event = self.close_event;
+        close_event(event).check(())
     }
 
-    pub fn handle_protocol<T: HasGuid>(&self, handle: Handle) -> Result<&T, Status> {
+    /// Returns `false` if the event is not signaled yet
+    pub fn check_event(&self, event: Event) -> Result<bool, Status> {
+        let check_event = self.check_event;
+        match check_event(event) {
+            Status::NOT_READY => Ok(false),
+            status => status.check(true),
+        }
+    }
+
+    /// Deprecated by the specification, prefer `open_protocol`
+    pub fn handle_protocol<T: Protocol + ?Sized>(&self, handle: Handle) -> Result<&T, Status> {
         let handle_protocol = self.handle_protocol;
         let mut implementation: *const () = ptr::null();
         let guid = T::GUID;
         handle_protocol(handle, &guid, &mut implementation)
-            .check(unsafe { &*(implementation as *const T) })
+            .check_map(|| unsafe { &*T::from_interface(implementation as _) })
+    }
+
+    /// Installs the interface on the `handle`, or on a new handle if it is `None`,
+    /// the interface must outlive the installation and be writable by the consumers,
+    /// hence `&'static mut`, e.g. from `Box::leak`
+    pub fn install_protocol_interface<'a, T>(
+        &'a self,
+        handle: Option<Handle>,
+        interface: &'static mut T,
+    ) -> Result<InstalledInterface<'a, T>, Status>
+    where
+        T: HasGuid + ?Sized,
+    {
+        let install_protocol_interface = self.install_protocol_interface;
+        let mut handle = handle.unwrap_or(Handle::NULL);
+        let guid = T::GUID;
+        let interface = interface as *mut T;
+        install_protocol_interface(&mut handle, &guid, NATIVE_INTERFACE, interface as *mut ())
+            .check_map(|| InstalledInterface {
+                boot_services: self,
+                handle,
+                interface,
+            })
+    }
+
+    /// The `new_interface` must outlive the installation, see `install_protocol_interface`,
+    /// the old interface is identified by the pointer
+    pub fn reinstall_protocol_interface<T>(
+        &self,
+        handle: Handle,
+        old_interface: *const T,
+        new_interface: &'static mut T,
+    ) -> Result<(), Status>
+    where
+        T: HasGuid + ?Sized,
+    {
+        self.reinstall_protocol_interface_raw(handle, old_interface, new_interface)
     }
 
-    pub fn locate_handle_buffer(&self, search_key: SearchKey) -> Result<&[Handle], Status> {
+    fn reinstall_protocol_interface_raw<T>(
+        &self,
+        handle: Handle,
+        old_interface: *const T,
+        new_interface: *mut T,
+    ) -> Result<(), Status>
+    where
+        T: HasGuid + ?Sized,
+    {
+        let reinstall_protocol_interface = self.reinstall_protocol_interface;
+        let guid = T::GUID;
+        reinstall_protocol_interface(
+            handle,
+            &guid,
+            old_interface as *const () as *mut (),
+            new_interface as *mut (),
+        ).check(())
+    }
+
+    /// The interface is identified by the pointer
+    pub fn uninstall_protocol_interface<T>(
+        &self,
+        handle: Handle,
+        interface: *const T,
+    ) -> Result<(), Status>
+    where
+        T: HasGuid + ?Sized,
+    {
+        let uninstall_protocol_interface = self.uninstall_protocol_interface;
+        let guid = T::GUID;
+        let interface = interface as *const () as *mut ();
+        uninstall_protocol_interface(handle, &guid, interface).check(())
+    }
+
+    /// Installs the tuple of interfaces, either all of them or none,
+    /// fails if the device path is already installed on another handle
+    pub fn install_multiple_protocol_interfaces<'a, L>(
+        &'a self,
+        handle: Option<Handle>,
+        interfaces: L,
+    ) -> Result<InstalledInterfaces<'a, L>, Status>
+    where
+        L: InterfaceList + 'a,
+    {
+        let mut handle = handle.unwrap_or(Handle::NULL);
+        let interfaces = interfaces.into_raw();
+        let status = L::install(interfaces, self, &mut handle);
+        // the guard must not be created on failure, it would uninstall on drop
+        status.check_map(|| InstalledInterfaces {
+            boot_services: self,
+            handle,
+            interfaces,
+        })
+    }
+
+    /// The `agent` is the image handle of the caller, the `controller` is required
+    /// when a driver opens the protocol, use `test_protocol` for `TEST_PROTOCOL`
+    pub fn open_protocol<T>(
+        &self,
+        handle: Handle,
+        agent: Handle,
+        controller: Option<Handle>,
+        attributes: OpenProtocolAttributes,
+    ) -> Result<ScopedProtocol<'_, T>, Status>
+    where
+        T: Protocol + ?Sized,
+    {
+        if attributes.contains(OpenProtocolAttributes::TEST_PROTOCOL) {
+            return Err(Status::INVALID_PARAMETER);
+        }
+
+        let open_protocol = self.open_protocol;
+        let mut interface = ptr::null_mut();
+        let controller = controller.unwrap_or(Handle::NULL);
+        let guid = T::GUID;
+        open_protocol(
+            handle,
+            &guid,
+            &mut interface,
+            agent,
+            controller,
+            attributes.bits(),
+        ).check(())?;
+        Ok(ScopedProtocol {
+            boot_services: self,
+            interface: unsafe { T::from_interface(interface) },
+            handle,
+            agent,
+            controller,
+            exclusive: attributes.contains(OpenProtocolAttributes::EXCLUSIVE),
+        })
+    }
+
+    /// Returns `false` if the protocol is not installed on the `handle`
+    pub fn test_protocol<T>(
+        &self,
+        handle: Handle,
+        agent: Handle,
+        controller: Option<Handle>,
+    ) -> Result<bool, Status>
+    where
+        T: HasGuid + ?Sized,
+    {
+        let open_protocol = self.open_protocol;
+        let controller = controller.unwrap_or(Handle::NULL);
+        let guid = T::GUID;
+        match open_protocol(
+            handle,
+            &guid,
+            ptr::null_mut(),
+            agent,
+            controller,
+            OpenProtocolAttributes::TEST_PROTOCOL.bits(),
+        ) {
+            Status::UNSUPPORTED => Ok(false),
+            status => status.check(true),
+        }
+    }
+
+    pub fn close_protocol<T>(
+        &self,
+        handle: Handle,
+        agent: Handle,
+        controller: Option<Handle>,
+    ) -> Result<(), Status>
+    where
+        T: HasGuid + ?Sized,
+    {
+        let close_protocol = self.close_protocol;
+        let controller = controller.unwrap_or(Handle::NULL);
+        let guid = T::GUID;
+        close_protocol(handle, &guid, agent, controller).check(())
+    }
+
+    /// The guids are owned by the firmware, only the array is allocated from the pool
+    pub fn protocols_per_handle(&self, handle: Handle) -> Result<PoolSlice<'_, &Guid>, Status> {
+        let protocols_per_handle = self.protocols_per_handle;
+        let mut buffer: *mut *const Guid = ptr::null_mut();
+        let mut count: Word = 0;
+        protocols_per_handle(handle, &mut buffer, &mut count)
+            .check_map(|| unsafe { PoolSlice::from_raw(self, buffer as _, count) })
+    }
+
+    pub fn open_protocol_information<T>(
+        &self,
+        handle: Handle,
+    ) -> Result<PoolSlice<'_, OpenProtocolInfoEntry>, Status>
+    where
+        T: HasGuid + ?Sized,
+    {
+        self.open_protocol_information_by_guid(handle, &T::GUID)
+    }
+
+    /// For the guids returned by `protocols_per_handle`
+    pub fn open_protocol_information_by_guid(
+        &self,
+        handle: Handle,
+        guid: &Guid,
+    ) -> Result<PoolSlice<'_, OpenProtocolInfoEntry>, Status> {
+        let open_protocol_information = self.open_protocol_information;
+        let mut buffer = ptr::null_mut();
+        let mut count: Word = 0;
+        open_protocol_information(handle, guid, &mut buffer, &mut count)
+            .check_map(|| unsafe { PoolSlice::from_raw(self, buffer, count) })
+    }
+
+    /// The `event` is signaled every time the protocol is installed,
+    /// use `SearchKey::ByRegisterNotify` to find out the handles
+    pub fn register_protocol_notify<T>(&self, event: Event) -> Result<Registration, Status>
+    where
+        T: HasGuid + ?Sized,
+    {
+        let register_protocol_notify = self.register_protocol_notify;
+        let mut registration = Registration::NULL;
+        let guid = T::GUID;
+        register_protocol_notify(&guid, event, &mut registration).check(registration)
+    }
+
+    /// Creates the event and registers it for the protocol
+    pub fn protocol_notify<T>(&self) -> Result<ProtocolNotify<'_, T>, Status>
+    where
+        T: HasGuid + ?Sized,
+    {
+        let event = self.create_event(EventType::empty(), TPL_CALLBACK, None, ptr::null_mut())?;
+        match self.register_protocol_notify::<T>(event) {
+            Ok(registration) => Ok(ProtocolNotify {
+                boot_services: self,
+                event,
+                registration,
+                pending: Vec::new(),
+                phantom_data: PhantomData,
+            }),
+            Err(status) => {
+                let _ = self.close_event(event);
+                Err(status)
+            }
+        }
+    }
+
+    /// Returns the number of the matching handles, if it exceeds the length
+    /// of the `buffer` nothing is written, call it again with a bigger one
+    pub fn locate_handle(
+        &self,
+        search_key: SearchKey,
+        buffer: &mut [Handle],
+    ) -> Result<Word, Status> {
+        let locate_handle = self.locate_handle;
+        let (search_type, protocol, registration) = search_key.as_raw();
+        let mut buffer_size = mem::size_of_val(buffer);
+        match locate_handle(
+            search_type,
+            protocol,
+            registration,
+            &mut buffer_size,
+            buffer.as_mut_ptr(),
+        ) {
+            Status::NOT_FOUND => Ok(0),
+            Status::BUFFER_TOO_SMALL => Ok(buffer_size / mem::size_of::<Handle>()),
+            status => status.check(buffer_size / mem::size_of::<Handle>()),
+        }
+    }
+
+    pub fn locate_handle_buffer(
+        &self,
+        search_key: SearchKey,
+    ) -> Result<PoolSlice<'_, Handle>, Status> {
         let locate_handle_buffer = self.locate_handle_buffer;
+        let (search_type, protocol, registration) = search_key.as_raw();
         let mut no_handles: Word = 0;
-        let mut buffer: *const Handle = 0 as _;
-        match search_key {
-            SearchKey::AllHandles => locate_handle_buffer(
-                0,
-                ptr::null(),
-                Registration::NULL,
-                &mut no_handles,
-                &mut buffer,
-            ),
-            SearchKey::ByRegisterNotify(registration) => {
-                locate_handle_buffer(1, ptr::null(), registration, &mut no_handles, &mut buffer)
-            }
-            SearchKey::ByProtocol(guid) => {
-                locate_handle_buffer(2, &guid, Registration::NULL, &mut no_handles, &mut buffer)
-            }
-        }.check(unsafe { slice::from_raw_parts(buffer, no_handles as _) })
+        let mut buffer: *mut Handle = ptr::null_mut();
+        locate_handle_buffer(
+            search_type,
+            protocol,
+            registration,
+            &mut no_handles,
+            &mut buffer,
+        ).check_map(|| unsafe { PoolSlice::from_raw(self, buffer, no_handles) })
+    }
+
+    /// The first interface of the protocol, for the protocols installed only once
+    pub fn locate_protocol<T: Protocol + ?Sized>(&self) -> Result<&T, Status> {
+        let locate_protocol = self.locate_protocol;
+        let mut interface: *const () = ptr::null();
+        let guid = T::GUID;
+        locate_protocol(&guid, Registration::NULL, &mut interface)
+            .check_map(|| unsafe { &*T::from_interface(interface as _) })
+    }
+
+    /// The handle closest to the end of the `device_path` that supports the protocol,
+    /// and the rest of the `device_path` after the part matching the handle
+    pub fn locate_device_path<'a, T>(
+        &self,
+        device_path: &'a DevicePath,
+    ) -> Result<(Handle, &'a DevicePath), Status>
+    where
+        T: HasGuid + ?Sized,
+    {
+        let locate_device_path = self.locate_device_path;
+        let mut remaining = device_path.as_ptr();
+        let mut device = Handle::NULL;
+        let guid = T::GUID;
+        locate_device_path(&guid, &mut remaining, &mut device)
+            .check_map(|| (device, unsafe { DevicePath::from_ptr(remaining) }))
+    }
+
+    /// The `device_path` is where the image was loaded from, if any
+    pub fn load_image_from_buffer(
+        &self,
+        parent_image: Handle,
+        device_path: Option<&DevicePath>,
+        buffer: &[u8],
+    ) -> Result<Handle, Status> {
+        let load_image = self.load_image;
+        let device_path = device_path.map_or(ptr::null(), DevicePath::as_ptr);
+        let mut image = Handle::NULL;
+        load_image(
+            0,
+            parent_image,
+            device_path,
+            buffer.as_ptr(),
+            buffer.len(),
+            &mut image,
+        ).check(image)
+    }
+
+    /// The `boot_policy` means the request originates from the boot manager,
+    /// so the path may be a removable media or a network boot option
+    pub fn load_image_from_device_path(
+        &self,
+        parent_image: Handle,
+        device_path: &DevicePath,
+        boot_policy: bool,
+    ) -> Result<Handle, Status> {
+        let load_image = self.load_image;
+        let mut image = Handle::NULL;
+        load_image(
+            boot_policy as _,
+            parent_image,
+            device_path.as_ptr(),
+            ptr::null(),
+            0,
+            &mut image,
+        ).check(image)
+    }
+
+    /// Returns when the image exits, or returns `EFI_SUCCESS` from its entry point
+    pub fn start_image(&self, image: Handle) -> Result<(), ImageExit<'_>> {
+        let start_image = self.start_image;
+        let mut exit_data_size: Word = 0;
+        let mut exit_data: *mut Char16 = ptr::null_mut();
+        start_image(image, &mut exit_data_size, &mut exit_data)
+            .check(())
+            .map_err(|status| ImageExit {
+                status,
+                data: unsafe { PoolSlice::from_raw(self, exit_data as _, exit_data_size) },
+            })
+    }
+
+    pub fn unload_image(&self, image: Handle) -> Result<(), Status> {
+        let unload_image = self.unload_image;
+        unload_image(image).check(())
+    }
+
+    /// Terminates the `image`, the `message` is passed to the caller of `start_image`
+    /// only if the `status` is an error, panics if the firmware refuses to exit
+    pub fn exit(&self, image: Handle, status: Status, message: Option<&str>) -> ! {
+        let exit = self.exit;
+        let (exit_data, exit_data_size) = message
+            .filter(|_| status != Status::NULL)
+            .and_then(|message| {
+                let units = message
+                    .encode_utf16()
+                    .chain(Some(0))
+                    .collect::<Vec<Char16>>();
+                let size = mem::size_of_val(units.as_slice());
+                let raw = self.allocate_pool(MemoryType::LoaderData, size).ok()?;
+                let raw = raw as *mut Char16;
+                unsafe { ptr::copy_nonoverlapping(units.as_ptr(), raw, units.len()) };
+                Some((raw, size))
+            })
+            .unwrap_or((ptr::null_mut(), 0));
+        let status = exit(image, status, exit_data_size, exit_data);
+        if !exit_data.is_null() {
+            let _ = self.free_pool(exit_data as _);
+        }
+        panic!("failed to exit the image: {:?}", status)
     }
 
     pub fn exit_boot_services(&self, handle: Handle, map_key: Word) -> Result<(), Status> {
@@ -1,3 +1,4 @@
+use common;
 use common::HasGuid;
 
 use boot_services::BootServices;
@@ -6,49 +7,148 @@ use boot_services::SearchKey;
 use interface::simple_file_system::SimpleFileSystem;
 use interface::file::OpenMode;
 use interface::file::Attributes;
-use interface::file::File;
+use interface::file::FileHandle;
+use interface::loaded_image::LoadedImage;
 
-use common::Char16;
+use path::Path;
+use path::PathBuf;
+use path::ToPath;
 
 use common::PAGE_SIZE;
 use common::Address;
 use common::Status;
+use common::Handle;
 use boot_services::AllocateType;
 use boot_services::MemoryType;
 
 use array::Array;
 
+use pool::PoolSlice;
+
 use core::mem;
-use core::ops::Try;
-use core::option::NoneError;
+
+/// Error of the file lookup
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum FindError {
+    /// No volume contains the file
+    NotFound,
+    /// Returned by the protocol wrappers
+    Firmware(common::Error),
+}
+
+impl From<Status> for FindError {
+    fn from(status: Status) -> Self {
+        FindError::Firmware(status.into())
+    }
+}
+
+impl From<common::Error> for FindError {
+    fn from(error: common::Error) -> Self {
+        FindError::Firmware(error)
+    }
+}
 
 /// Tools
 pub trait BootServicesEx {
-    fn find_file(
+    /// The first volume containing the path, if none does, the first error
+    /// other than `NOT_FOUND` is returned, or `NotFound`
+    fn find_file<P>(
         &self,
-        filename: &[Char16],
+        path: &P,
         open_mode: OpenMode,
         attributes: Attributes,
-    ) -> Result<&File, NoneError>;
+    ) -> Result<FileHandle, FindError>
+    where
+        P: ToPath + ?Sized;
+    /// Every volume containing the path, the volumes where it is `NOT_FOUND` are skipped,
+    /// the other errors are yielded
+    fn find_file_all<P>(
+        &self,
+        path: &P,
+        open_mode: OpenMode,
+        attributes: Attributes,
+    ) -> Result<FoundFiles<'_>, FindError>
+    where
+        P: ToPath + ?Sized;
+    /// Looks at the volume the `image` was loaded from first, then at the others
+    /// only if the path is `NOT_FOUND` there or the device has no file system,
+    /// any other error of the boot volume is returned
+    fn find_file_on_boot_device<P>(
+        &self,
+        image: Handle,
+        path: &P,
+        open_mode: OpenMode,
+        attributes: Attributes,
+    ) -> Result<(Handle, FileHandle), FindError>
+    where
+        P: ToPath + ?Sized;
     fn alloc<T: Sized>(&self, length: usize, memory_type: MemoryType) -> Result<Array<T>, Status>;
     fn free<T: Sized>(&self, array: Array<T>) -> Result<(), Status>;
 }
 
 impl BootServicesEx for BootServices {
-    fn find_file(
+    fn find_file<P>(
+        &self,
+        path: &P,
+        open_mode: OpenMode,
+        attributes: Attributes,
+    ) -> Result<FileHandle, FindError>
+    where
+        P: ToPath + ?Sized,
+    {
+        first_found(self.find_file_all(path, open_mode, attributes)?).map(|(_, file)| file)
+    }
+
+    fn find_file_all<P>(
         &self,
-        filename: &[Char16],
+        path: &P,
         open_mode: OpenMode,
         attributes: Attributes,
-    ) -> Result<&File, NoneError> {
-        self.locate_handle_buffer(SearchKey::ByProtocol(SimpleFileSystem::GUID))
-            .map_err(|_| NoneError)?
-            .into_iter()
-            .flat_map(|handle| self.handle_protocol(*handle).ok())
-            .flat_map(|fs: &SimpleFileSystem| fs.open_volume().ok())
-            .flat_map(|volume| volume.open(filename, open_mode, attributes))
-            .next()
-            .into_result()
+    ) -> Result<FoundFiles<'_>, FindError>
+    where
+        P: ToPath + ?Sized,
+    {
+        let path = path.to_path().map_err(Status::from)?.into_owned();
+        let handles = self.locate_handle_buffer(SearchKey::ByProtocol(SimpleFileSystem::GUID))?;
+        Ok(FoundFiles {
+            boot_services: self,
+            handles,
+            index: 0,
+            path,
+            open_mode,
+            attributes,
+        })
+    }
+
+    fn find_file_on_boot_device<P>(
+        &self,
+        image: Handle,
+        path: &P,
+        open_mode: OpenMode,
+        attributes: Attributes,
+    ) -> Result<(Handle, FileHandle), FindError>
+    where
+        P: ToPath + ?Sized,
+    {
+        let device = self.handle_protocol::<LoadedImage>(image)?.device_handle();
+        let path = path.to_path().map_err(Status::from)?;
+        match self.handle_protocol::<SimpleFileSystem>(device) {
+            // loaded from a device without a file system, e.g. the network
+            Err(Status::UNSUPPORTED) => (),
+            Err(status) => return Err(status.into()),
+            Ok(fs) => match fs.open_volume()?.open(&path, open_mode, attributes) {
+                Ok(file) => return Ok((device, file)),
+                Err(common::Error::Status(Status::NOT_FOUND)) => (),
+                Err(error) => return Err(error.into()),
+            },
+        }
+        let others =
+            self.find_file_all(&*path, open_mode, attributes)?
+                .filter(|found| match *found {
+                    Ok((handle, _)) => handle != device,
+                    Err(_) => true,
+                });
+        first_found(others)
     }
 
     fn alloc<T: Sized>(&self, length: usize, memory_type: MemoryType) -> Result<Array<T>, Status> {
@@ -72,3 +172,64 @@ impl BootServicesEx for BootServices {
         self.free_pages(address, pages)
     }
 }
+
+fn open_on_volume(
+    boot_services: &BootServices,
+    handle: Handle,
+    path: &Path,
+    open_mode: OpenMode,
+    attributes: Attributes,
+) -> Result<FileHandle, common::Error> {
+    let fs: &SimpleFileSystem = boot_services.handle_protocol(handle)?;
+    fs.open_volume()?.open(path, open_mode, attributes)
+}
+
+// the first match, otherwise the first error
+fn first_found<I>(found: I) -> Result<(Handle, FileHandle), FindError>
+where
+    I: Iterator<Item = Result<(Handle, FileHandle), FindError>>,
+{
+    let mut first_error = None;
+    for result in found {
+        match result {
+            Ok(found) => return Ok(found),
+            Err(error) => {
+                first_error.get_or_insert(error);
+            }
+        }
+    }
+    Err(first_error.unwrap_or(FindError::NotFound))
+}
+
+/// Iterator returned by `BootServicesEx::find_file_all`
+pub struct FoundFiles<'a> {
+    boot_services: &'a BootServices,
+    handles: PoolSlice<'a, Handle>,
+    index: usize,
+    path: PathBuf,
+    open_mode: OpenMode,
+    attributes: Attributes,
+}
+
+impl<'a> Iterator for FoundFiles<'a> {
+    type Item = Result<(Handle, FileHandle), FindError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(&handle) = self.handles.get(self.index) {
+            self.index += 1;
+            let file = open_on_volume(
+                self.boot_services,
+                handle,
+                &self.path,
+                self.open_mode,
+                self.attributes,
+            );
+            match file {
+                Ok(file) => return Some(Ok((handle, file))),
+                Err(common::Error::Status(Status::NOT_FOUND)) => continue,
+                Err(error) => return Some(Err(error.into())),
+            }
+        }
+        None
+    }
+}
@@ -1,3 +1,7 @@
+use core::mem;
+use core::fmt;
+use core::char;
+
 pub type Word = usize;
 pub type Char16 = u16;
 pub type Bool = u8;
@@ -8,12 +12,20 @@ pub struct Handle {
     raw: Word,
 }
 
+impl Handle {
+    pub const NULL: Self = Handle { raw: 0 };
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub struct Event {
     raw: Word,
 }
 
+impl Event {
+    pub const NULL: Self = Event { raw: 0 };
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub struct Registration {
@@ -25,13 +37,39 @@ impl Registration {
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct Status {
     pub raw: Word,
 }
 
 impl Status {
+    const ERROR_BIT: Word = 1 << (mem::size_of::<Word>() * 8 - 1);
+
     pub const NULL: Self = Status { raw: 0 };
+    pub const INVALID_PARAMETER: Self = Status {
+        raw: Self::ERROR_BIT | 2,
+    };
+    pub const UNSUPPORTED: Self = Status {
+        raw: Self::ERROR_BIT | 3,
+    };
+    pub const BUFFER_TOO_SMALL: Self = Status {
+        raw: Self::ERROR_BIT | 5,
+    };
+    pub const NOT_READY: Self = Status {
+        raw: Self::ERROR_BIT | 6,
+    };
+    pub const OUT_OF_RESOURCES: Self = Status {
+        raw: Self::ERROR_BIT | 9,
+    };
+    pub const VOLUME_CORRUPTED: Self = Status {
+        raw: Self::ERROR_BIT | 10,
+    };
+    pub const VOLUME_FULL: Self = Status {
+        raw: Self::ERROR_BIT | 11,
+    };
+    pub const NOT_FOUND: Self = Status {
+        raw: Self::ERROR_BIT | 14,
+    };
 }
 
 impl Status {
@@ -66,10 +104,71 @@ impl Status {
     }
 }
 
+/// Error of a protocol wrapper
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Error {
+    /// Returned by the firmware
+    Status(Status),
+    /// The method is not available in the revision of the protocol found
+    Unsupported { required: u64, found: u64 },
+}
+
+impl Error {
+    /// `UNSUPPORTED` for the revision mismatch
+    pub fn status(&self) -> Status {
+        match *self {
+            Error::Status(status) => status,
+            Error::Unsupported { .. } => Status::UNSUPPORTED,
+        }
+    }
+}
+
+impl From<Status> for Error {
+    fn from(status: Status) -> Self {
+        Error::Status(status)
+    }
+}
+
+/// Protocol whose set of methods depends on its revision,
+/// every method checks the revision it requires before calling the firmware
+pub trait Revision {
+    fn revision(&self) -> u64;
+
+    fn require_revision(&self, required: u64) -> Result<(), Error> {
+        let found = self.revision();
+        if found >= required {
+            Ok(())
+        } else {
+            Err(Error::Unsupported { required, found })
+        }
+    }
+}
+
 #[repr(C)]
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct Guid(pub u32, pub u16, pub u16, pub [u8; 8]);
 
+/// The registry format, e.g. `964e5b22-6459-11d2-8e39-00a0c969723b`
+impl fmt::Display for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Guid(a, b, c, ref d) = *self;
+        write!(
+            f,
+            "{:08x}-{:04x}-{:04x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+            a, b, c, d[0], d[1], d[2], d[3], d[4], d[5], d[6], d[7]
+        )
+    }
+}
+
+/// Decodes the units of a firmware string, which is UTF-16 in practice,
+/// invalid sequences are replaced with `U+FFFD`
+pub fn decode_ucs2<I>(units: I) -> impl Iterator<Item = char>
+where
+    I: IntoIterator<Item = u16>,
+{
+    char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub struct Header {
@@ -86,27 +185,57 @@ pub trait HasGuid {
     const GUID: Guid;
 }
 
+/// The interface reachable through the pointer the firmware gives,
+/// implemented for every sized protocol, the unsized ones compute their length
+pub trait Protocol: HasGuid {
+    /// # Safety
+    ///
+    /// The `raw` must point to the valid interface
+    unsafe fn from_interface(raw: *mut ()) -> *mut Self;
+}
+
+impl<T> Protocol for T
+where
+    T: HasGuid,
+{
+    unsafe fn from_interface(raw: *mut ()) -> *mut Self {
+        raw as _
+    }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub struct Address {
-    // maybe usize
+    // physical addresses are 64 bit wide even on 32 bit targets
     raw: u64,
 }
 
 impl Address {
     pub const NULL: Self = Address { raw: 0 };
 
+    pub fn raw(&self) -> u64 {
+        self.raw
+    }
+
+    /// # Safety
+    ///
+    /// The address must be valid for `T`
     pub unsafe fn cast<T>(self) -> *mut T
     where
         T: Sized,
     {
-        self.raw as _
+        self.raw as usize as _
     }
 
+    /// # Safety
+    ///
+    /// The `raw` must be a physical address, it is true while the memory is identity mapped
     pub unsafe fn from_raw<T>(raw: *mut T) -> Self
     where
         T: Sized,
     {
-        Address { raw: raw as _ }
+        Address {
+            raw: raw as usize as _,
+        }
     }
 }
@@ -21,10 +21,9 @@ impl FindTable for Array<ConfigurationTable> {
     where
         T: HasGuid,
     {
-        (0..self.length())
-            .into_iter()
-            .find(|&index| self[index].vendor_guid == T::GUID)
-            .map(|index| unsafe { Pointer::from_raw(self[index].vendor_table as _) })
+        self.iter()
+            .find(|table| table.vendor_guid == T::GUID)
+            .map(|table| unsafe { Pointer::from_raw(table.vendor_table as _) })
     }
 }
 
@@ -0,0 +1,644 @@
+use common::Guid;
+
+use path::Path;
+
+use super::node::Acpi;
+use super::node::Bbs;
+use super::node::End;
+use super::node::Hardware;
+use super::node::Media;
+use super::node::Messaging;
+use super::node::Node;
+use super::node::PartitionFormat;
+use super::node::PartitionSignature;
+use super::DevicePath;
+use super::DevicePathError;
+use super::DevicePathNode;
+use super::END_ENTIRE_SUB_TYPE;
+use super::END_INSTANCE_SUB_TYPE;
+use super::END_TYPE;
+use super::HEADER_SIZE;
+
+use alloc::vec::Vec;
+
+fn guid_bytes(guid: &Guid) -> [u8; 16] {
+    let Guid(a, b, c, d) = *guid;
+    let mut bytes = [0; 16];
+    bytes[..4].copy_from_slice(&a.to_le_bytes());
+    bytes[4..6].copy_from_slice(&b.to_le_bytes());
+    bytes[6..8].copy_from_slice(&c.to_le_bytes());
+    bytes[8..].copy_from_slice(&d);
+    bytes
+}
+
+// the node data is emitted in pieces, so it is encoded without allocation
+type Emit<'e> = &'e mut dyn FnMut(&[u8]);
+
+impl<'a> Hardware<'a> {
+    fn encode(&self, emit: Emit) -> u8 {
+        match *self {
+            Hardware::Pci { function, device } => {
+                emit(&[function, device]);
+                0x01
+            }
+            Hardware::PcCard { function } => {
+                emit(&[function]);
+                0x02
+            }
+            Hardware::MemoryMapped {
+                memory_type,
+                start,
+                end,
+            } => {
+                emit(&memory_type.to_le_bytes());
+                emit(&start.to_le_bytes());
+                emit(&end.to_le_bytes());
+                0x03
+            }
+            Hardware::Vendor { ref guid, data } => {
+                emit(&guid_bytes(guid));
+                emit(data);
+                0x04
+            }
+            Hardware::Controller { controller } => {
+                emit(&controller.to_le_bytes());
+                0x05
+            }
+            Hardware::Bmc {
+                interface_type,
+                base_address,
+            } => {
+                emit(&[interface_type]);
+                emit(&base_address.to_le_bytes());
+                0x06
+            }
+            Hardware::Unknown { sub_type, data } => {
+                emit(data);
+                sub_type
+            }
+        }
+    }
+}
+
+impl<'a> Acpi<'a> {
+    fn encode(&self, emit: Emit) -> u8 {
+        match *self {
+            Acpi::Acpi { hid, uid } => {
+                emit(&hid.to_le_bytes());
+                emit(&uid.to_le_bytes());
+                0x01
+            }
+            Acpi::Expanded {
+                hid,
+                uid,
+                cid,
+                hid_str,
+                uid_str,
+                cid_str,
+            } => {
+                emit(&hid.to_le_bytes());
+                emit(&uid.to_le_bytes());
+                emit(&cid.to_le_bytes());
+                for string in &[hid_str, uid_str, cid_str] {
+                    emit(string);
+                    emit(&[0]);
+                }
+                0x02
+            }
+            Acpi::Adr { adr } => {
+                emit(adr);
+                0x03
+            }
+            Acpi::Nvdimm { handle } => {
+                emit(&handle.to_le_bytes());
+                0x04
+            }
+            Acpi::Unknown { sub_type, data } => {
+                emit(data);
+                sub_type
+            }
+        }
+    }
+}
+
+impl<'a> Messaging<'a> {
+    fn encode(&self, emit: Emit) -> u8 {
+        match *self {
+            Messaging::Atapi {
+                primary_secondary,
+                slave_master,
+                lun,
+            } => {
+                emit(&[primary_secondary, slave_master]);
+                emit(&lun.to_le_bytes());
+                0x01
+            }
+            Messaging::Scsi { target, lun } => {
+                emit(&target.to_le_bytes());
+                emit(&lun.to_le_bytes());
+                0x02
+            }
+            Messaging::FibreChannel {
+                world_wide_name,
+                lun,
+            } => {
+                emit(&[0; 4]);
+                emit(&world_wide_name.to_le_bytes());
+                emit(&lun.to_le_bytes());
+                0x03
+            }
+            Messaging::Usb {
+                parent_port,
+                interface,
+            } => {
+                emit(&[parent_port, interface]);
+                0x05
+            }
+            Messaging::Vendor { ref guid, data } => {
+                emit(&guid_bytes(guid));
+                emit(data);
+                0x0a
+            }
+            Messaging::MacAddress {
+                ref address,
+                interface_type,
+            } => {
+                emit(address);
+                emit(&[interface_type]);
+                0x0b
+            }
+            Messaging::Ipv4 {
+                ref local_address,
+                ref remote_address,
+                local_port,
+                remote_port,
+                protocol,
+                static_address,
+                ref gateway_address,
+                ref subnet_mask,
+            } => {
+                emit(local_address);
+                emit(remote_address);
+                emit(&local_port.to_le_bytes());
+                emit(&remote_port.to_le_bytes());
+                emit(&protocol.to_le_bytes());
+                emit(&[static_address as u8]);
+                emit(gateway_address);
+                emit(subnet_mask);
+                0x0c
+            }
+            Messaging::Ipv6 {
+                ref local_address,
+                ref remote_address,
+                local_port,
+                remote_port,
+                protocol,
+                address_origin,
+                prefix_length,
+                ref gateway_address,
+            } => {
+                emit(local_address);
+                emit(remote_address);
+                emit(&local_port.to_le_bytes());
+                emit(&remote_port.to_le_bytes());
+                emit(&protocol.to_le_bytes());
+                emit(&[address_origin, prefix_length]);
+                emit(gateway_address);
+                0x0d
+            }
+            Messaging::UsbClass {
+                vendor_id,
+                product_id,
+                class,
+                subclass,
+                protocol,
+            } => {
+                emit(&vendor_id.to_le_bytes());
+                emit(&product_id.to_le_bytes());
+                emit(&[class, subclass, protocol]);
+                0x0f
+            }
+            Messaging::UsbWwid {
+                interface,
+                vendor_id,
+                product_id,
+                serial_number,
+            } => {
+                emit(&interface.to_le_bytes());
+                emit(&vendor_id.to_le_bytes());
+                emit(&product_id.to_le_bytes());
+                emit(serial_number.as_bytes());
+                0x10
+            }
+            Messaging::DeviceLogicalUnit { lun } => {
+                emit(&[lun]);
+                0x11
+            }
+            Messaging::Sata {
+                hba_port,
+                port_multiplier_port,
+                lun,
+            } => {
+                emit(&hba_port.to_le_bytes());
+                emit(&port_multiplier_port.to_le_bytes());
+                emit(&lun.to_le_bytes());
+                0x12
+            }
+            Messaging::Vlan { id } => {
+                emit(&id.to_le_bytes());
+                0x14
+            }
+            Messaging::Nvme {
+                namespace_id,
+                ref eui64,
+            } => {
+                emit(&namespace_id.to_le_bytes());
+                emit(eui64);
+                0x17
+            }
+            Messaging::Uri { uri } => {
+                emit(uri);
+                0x18
+            }
+            Messaging::Ufs { target, lun } => {
+                emit(&[target, lun]);
+                0x19
+            }
+            Messaging::Sd { slot } => {
+                emit(&[slot]);
+                0x1a
+            }
+            Messaging::Emmc { slot } => {
+                emit(&[slot]);
+                0x1d
+            }
+            Messaging::Unknown { sub_type, data } => {
+                emit(data);
+                sub_type
+            }
+        }
+    }
+}
+
+impl<'a> Media<'a> {
+    fn encode(&self, emit: Emit) -> u8 {
+        match *self {
+            Media::HardDrive {
+                partition_number,
+                partition_start,
+                partition_size,
+                signature,
+                format,
+            } => {
+                emit(&partition_number.to_le_bytes());
+                emit(&partition_start.to_le_bytes());
+                emit(&partition_size.to_le_bytes());
+                let (signature_type, signature) = match signature {
+                    PartitionSignature::None => (0x00, [0; 16]),
+                    PartitionSignature::Mbr(signature) => {
+                        let mut bytes = [0; 16];
+                        bytes[..4].copy_from_slice(&signature.to_le_bytes());
+                        (0x01, bytes)
+                    }
+                    PartitionSignature::Guid(ref guid) => (0x02, guid_bytes(guid)),
+                    PartitionSignature::Unknown(signature_type, signature) => {
+                        (signature_type, signature)
+                    }
+                };
+                let format = match format {
+                    PartitionFormat::Mbr => 0x01,
+                    PartitionFormat::Gpt => 0x02,
+                    PartitionFormat::Unknown(format) => format,
+                };
+                emit(&signature);
+                emit(&[format, signature_type]);
+                0x01
+            }
+            Media::CdRom {
+                boot_entry,
+                partition_start,
+                partition_size,
+            } => {
+                emit(&boot_entry.to_le_bytes());
+                emit(&partition_start.to_le_bytes());
+                emit(&partition_size.to_le_bytes());
+                0x02
+            }
+            Media::Vendor { ref guid, data } => {
+                emit(&guid_bytes(guid));
+                emit(data);
+                0x03
+            }
+            Media::FilePath { path } => {
+                for c in path.units() {
+                    emit(&c.to_le_bytes());
+                }
+                emit(&[0, 0]);
+                0x04
+            }
+            Media::Protocol { ref guid } => {
+                emit(&guid_bytes(guid));
+                0x05
+            }
+            Media::FirmwareFile { ref name } => {
+                emit(&guid_bytes(name));
+                0x06
+            }
+            Media::FirmwareVolume { ref name } => {
+                emit(&guid_bytes(name));
+                0x07
+            }
+            Media::RelativeOffsetRange { start, end } => {
+                emit(&[0; 4]);
+                emit(&start.to_le_bytes());
+                emit(&end.to_le_bytes());
+                0x08
+            }
+            Media::RamDisk {
+                start,
+                end,
+                ref disk_type,
+                instance,
+            } => {
+                emit(&start.to_le_bytes());
+                emit(&end.to_le_bytes());
+                emit(&guid_bytes(disk_type));
+                emit(&instance.to_le_bytes());
+                0x09
+            }
+            Media::Unknown { sub_type, data } => {
+                emit(data);
+                sub_type
+            }
+        }
+    }
+}
+
+impl<'a> Bbs<'a> {
+    fn encode(&self, emit: Emit) -> u8 {
+        match *self {
+            Bbs::Bbs {
+                device_type,
+                status_flag,
+                description,
+            } => {
+                emit(&device_type.to_le_bytes());
+                emit(&status_flag.to_le_bytes());
+                emit(description);
+                emit(&[0]);
+                0x01
+            }
+            Bbs::Unknown { sub_type, data } => {
+                emit(data);
+                sub_type
+            }
+        }
+    }
+}
+
+impl<'a> Node<'a> {
+    // returns the sub type
+    fn encode(&self, emit: Emit) -> u8 {
+        match *self {
+            Node::Hardware(ref node) => node.encode(emit),
+            Node::Acpi(ref node) => node.encode(emit),
+            Node::Messaging(ref node) => node.encode(emit),
+            Node::Media(ref node) => node.encode(emit),
+            Node::Bbs(ref node) => node.encode(emit),
+            Node::End(End::Instance) => END_INSTANCE_SUB_TYPE,
+            Node::End(End::Entire) => END_ENTIRE_SUB_TYPE,
+            Node::Unknown { sub_type, data, .. } => {
+                emit(data);
+                sub_type
+            }
+        }
+    }
+}
+
+enum Storage<'a> {
+    Buffer { buffer: &'a mut [u8], length: usize },
+    Vec { vec: &'a mut Vec<u8>, start: usize },
+}
+
+/// Appends the nodes into the caller buffer or the vector,
+/// `finish` terminates the path with the end of entire path node
+pub struct DevicePathBuilder<'a> {
+    storage: Storage<'a>,
+}
+
+impl<'a> DevicePathBuilder<'a> {
+    pub fn with_buffer(buffer: &'a mut [u8]) -> Self {
+        DevicePathBuilder {
+            storage: Storage::Buffer { buffer, length: 0 },
+        }
+    }
+
+    /// The path is appended after the current content of the `vec`
+    pub fn with_vec(vec: &'a mut Vec<u8>) -> Self {
+        let start = vec.len();
+        DevicePathBuilder {
+            storage: Storage::Vec { vec, start },
+        }
+    }
+
+    // the `write` gets the function emitting the data
+    fn write<F>(&mut self, device_type: u8, write: F) -> Result<(), DevicePathError>
+    where
+        F: Fn(Emit) -> u8,
+    {
+        let mut data_length = 0;
+        let sub_type = write(&mut |data: &[u8]| data_length += data.len());
+        let length = HEADER_SIZE + data_length;
+        if length > u16::MAX as usize {
+            return Err(DevicePathError::NodeTooLong);
+        }
+        let header = [device_type, sub_type, length as u8, (length >> 8) as u8];
+
+        match self.storage {
+            Storage::Buffer {
+                ref mut buffer,
+                length: ref mut position,
+            } => {
+                // keep the room for the end node
+                if buffer.len() - *position < length + HEADER_SIZE {
+                    return Err(DevicePathError::BufferTooSmall);
+                }
+                let mut offset = *position;
+                let mut copy = |data: &[u8]| {
+                    buffer[offset..(offset + data.len())].copy_from_slice(data);
+                    offset += data.len();
+                };
+                copy(&header);
+                write(&mut copy);
+                *position = offset;
+            }
+            Storage::Vec { ref mut vec, .. } => {
+                vec.reserve(length);
+                vec.extend_from_slice(&header);
+                write(&mut |data: &[u8]| vec.extend_from_slice(data));
+            }
+        }
+        Ok(())
+    }
+
+    /// The end of entire path is written by `finish`, pushing it is an error
+    pub fn push(&mut self, node: &Node) -> Result<&mut Self, DevicePathError> {
+        if let Node::End(End::Entire) = *node {
+            return Err(DevicePathError::UnexpectedEnd);
+        }
+        self.write(node.device_type(), |emit| node.encode(emit))?;
+        Ok(self)
+    }
+
+    /// Copies the node as is
+    pub fn push_node(&mut self, node: &DevicePathNode) -> Result<&mut Self, DevicePathError> {
+        self.push_raw(node.device_type(), node.sub_type(), node.data())
+    }
+
+    pub fn push_raw(
+        &mut self,
+        device_type: u8,
+        sub_type: u8,
+        data: &[u8],
+    ) -> Result<&mut Self, DevicePathError> {
+        if device_type == END_TYPE && sub_type == END_ENTIRE_SUB_TYPE {
+            return Err(DevicePathError::UnexpectedEnd);
+        }
+        self.write(device_type, |emit| {
+            emit(data);
+            sub_type
+        })?;
+        Ok(self)
+    }
+
+    /// The file path node, relative to the device
+    pub fn push_file_path(&mut self, path: &Path) -> Result<&mut Self, DevicePathError> {
+        self.write(super::node::MEDIA_TYPE, |emit| {
+            for &c in path.as_slice() {
+                emit(&c.to_le_bytes());
+            }
+            emit(&[0, 0]);
+            0x04
+        })?;
+        Ok(self)
+    }
+
+    /// Copies the nodes of the `device_path`, without its end node
+    pub fn append(&mut self, device_path: &DevicePath) -> Result<&mut Self, DevicePathError> {
+        for node in device_path.nodes() {
+            self.push_node(node)?;
+        }
+        Ok(self)
+    }
+
+    /// Starts the next instance of the multi-instance path
+    pub fn end_instance(&mut self) -> Result<&mut Self, DevicePathError> {
+        self.push(&Node::End(End::Instance))
+    }
+
+    pub fn finish(self) -> Result<&'a DevicePath, DevicePathError> {
+        let end = [END_TYPE, END_ENTIRE_SUB_TYPE, HEADER_SIZE as u8, 0];
+        let raw: &'a [u8] = match self.storage {
+            Storage::Buffer { buffer, length } => {
+                // the room is checked by `write`, but the buffer might be too small from start
+                let buffer = buffer
+                    .get_mut(..(length + HEADER_SIZE))
+                    .ok_or(DevicePathError::BufferTooSmall)?;
+                buffer[length..].copy_from_slice(&end);
+                buffer
+            }
+            Storage::Vec { vec, start } => {
+                vec.extend_from_slice(&end);
+                &vec[start..]
+            }
+        };
+        Ok(DevicePath::from_bytes_unchecked(raw))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::node::Hardware;
+    use super::super::node::Node;
+    use super::super::DevicePathError;
+    use super::DevicePathBuilder;
+
+    use path::Path;
+
+    use alloc::vec::Vec;
+
+    const PCI: Node<'static> = Node::Hardware(Hardware::Pci {
+        function: 1,
+        device: 2,
+    });
+
+    #[test]
+    fn buffer() {
+        let mut buffer = [0; 16];
+        let mut builder = DevicePathBuilder::with_buffer(&mut buffer);
+        builder.push(&PCI).unwrap();
+        let path = builder.finish().unwrap();
+        assert_eq!(
+            path.as_bytes(),
+            &[0x01, 0x01, 0x06, 0x00, 0x01, 0x02, 0x7f, 0xff, 0x04, 0x00]
+        );
+    }
+
+    #[test]
+    fn buffer_too_small() {
+        // the node fits, but the end node would not
+        let mut buffer = [0; 8];
+        let mut builder = DevicePathBuilder::with_buffer(&mut buffer);
+        assert_eq!(
+            builder.push(&PCI).err(),
+            Some(DevicePathError::BufferTooSmall)
+        );
+        let mut buffer = [0; 2];
+        let builder = DevicePathBuilder::with_buffer(&mut buffer);
+        assert_eq!(
+            builder.finish().err(),
+            Some(DevicePathError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn vec_appends() {
+        let mut vec = vec![0xaa];
+        {
+            let mut builder = DevicePathBuilder::with_vec(&mut vec);
+            builder.push(&PCI).unwrap().end_instance().unwrap();
+            let path = builder.finish().unwrap();
+            assert_eq!(path.nodes().count(), 2);
+        }
+        assert_eq!(
+            vec,
+            [
+                0xaa, 0x01, 0x01, 0x06, 0x00, 0x01, 0x02, 0x7f, 0x01, 0x04, 0x00, 0x7f, 0xff, 0x04,
+                0x00
+            ]
+        );
+    }
+
+    #[test]
+    fn end_is_written_by_finish() {
+        let mut vec = Vec::new();
+        let mut builder = DevicePathBuilder::with_vec(&mut vec);
+        assert_eq!(
+            builder.push_raw(0x7f, 0xff, &[]).err(),
+            Some(DevicePathError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn file_path() {
+        let name = "\\a".encode_utf16().collect::<Vec<_>>();
+        let mut vec = Vec::new();
+        let mut builder = DevicePathBuilder::with_vec(&mut vec);
+        builder.push_file_path(Path::new(&name).unwrap()).unwrap();
+        let path = builder.finish().unwrap();
+        assert_eq!(
+            path.as_bytes(),
+            &[0x04, 0x04, 0x0a, 0x00, b'\\', 0x00, b'a', 0x00, 0x00, 0x00, 0x7f, 0xff, 0x04, 0x00]
+        );
+    }
+}
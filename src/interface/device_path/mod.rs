@@ -0,0 +1,320 @@
+use common::Error;
+use common::Guid;
+use common::HasGuid;
+use common::Protocol;
+
+use boot_services::BootServices;
+
+use pool::PoolBox;
+use common::Status;
+
+use core::borrow::Borrow;
+use core::fmt;
+use core::ops::Deref;
+use core::ptr;
+use core::slice;
+
+use alloc::borrow::ToOwned;
+use alloc::vec::Vec;
+
+mod builder;
+pub mod node;
+mod text;
+
+pub use self::builder::DevicePathBuilder;
+
+use self::node::Node;
+
+const HEADER_SIZE: usize = 4;
+
+const END_TYPE: u8 = 0x7f;
+const END_INSTANCE_SUB_TYPE: u8 = 0x01;
+const END_ENTIRE_SUB_TYPE: u8 = 0xff;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DevicePathError {
+    /// The node at the `offset` is shorter than its header or exceeds the buffer
+    InvalidLength {
+        offset: usize,
+    },
+    /// There is no end of entire path node
+    MissingEnd,
+    /// The end of entire path node is only written when the path is finished
+    UnexpectedEnd,
+    /// The node does not fit in 64 kilobytes
+    NodeTooLong,
+    BufferTooSmall,
+    /// The text of the node at the byte `position` is not recognized
+    InvalidText {
+        position: usize,
+    },
+}
+
+impl From<DevicePathError> for Status {
+    fn from(error: DevicePathError) -> Self {
+        match error {
+            DevicePathError::BufferTooSmall => Status::BUFFER_TOO_SMALL,
+            _ => Status::INVALID_PARAMETER,
+        }
+    }
+}
+
+impl From<DevicePathError> for Error {
+    fn from(error: DevicePathError) -> Self {
+        Error::Status(error.into())
+    }
+}
+
+impl fmt::Display for DevicePathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DevicePathError::InvalidLength { offset } => {
+                write!(f, "invalid length of the device path node at {}", offset)
+            }
+            DevicePathError::MissingEnd => write!(f, "missing the end of the device path"),
+            DevicePathError::UnexpectedEnd => write!(f, "unexpected end of the device path"),
+            DevicePathError::NodeTooLong => write!(f, "the device path node is too long"),
+            DevicePathError::BufferTooSmall => write!(f, "the buffer is too small"),
+            DevicePathError::InvalidText { position } => {
+                write!(f, "invalid device path text at {}", position)
+            }
+        }
+    }
+}
+
+// the length of the node at the beginning of `raw`, if it is valid
+fn node_length(raw: &[u8]) -> Option<usize> {
+    if raw.len() < HEADER_SIZE {
+        return None;
+    }
+    let length = u16::from_le_bytes([raw[2], raw[3]]) as usize;
+    if length < HEADER_SIZE || length > raw.len() {
+        None
+    } else {
+        Some(length)
+    }
+}
+
+/// Sequence of nodes terminated by the end of entire path node, which is included,
+/// the instances of the multi-instance path are separated by the end of instance node
+#[repr(transparent)]
+#[derive(Debug, Eq, PartialEq, Hash)]
+pub struct DevicePath {
+    inner: [u8],
+}
+
+impl DevicePath {
+    fn from_bytes_unchecked(raw: &[u8]) -> &Self {
+        unsafe { &*(raw as *const [u8] as *const DevicePath) }
+    }
+
+    /// Checks the lengths of the nodes, the bytes after the end node are ignored
+    pub fn new(raw: &[u8]) -> Result<&Self, DevicePathError> {
+        let mut offset = 0;
+        while offset < raw.len() {
+            let length =
+                node_length(&raw[offset..]).ok_or(DevicePathError::InvalidLength { offset })?;
+            let (device_type, sub_type) = (raw[offset], raw[offset + 1]);
+            offset += length;
+            if device_type == END_TYPE && sub_type == END_ENTIRE_SUB_TYPE {
+                return Ok(DevicePath::from_bytes_unchecked(&raw[..offset]));
+            }
+        }
+        Err(DevicePathError::MissingEnd)
+    }
+
+    /// # Safety
+    ///
+    /// The `raw` must point to the device path terminated by the end of entire path node
+    /// and live as long as `'a`, the walk stops at the first node with invalid length
+    pub unsafe fn from_ptr<'a>(raw: *const u8) -> &'a Self {
+        let mut length = 0;
+        loop {
+            let header = ptr::read(raw.add(length) as *const [u8; HEADER_SIZE]);
+            let node = u16::from_le_bytes([header[2], header[3]]) as usize;
+            if node < HEADER_SIZE {
+                length += HEADER_SIZE;
+                break;
+            }
+            length += node;
+            if header[0] == END_TYPE && header[1] == END_ENTIRE_SUB_TYPE {
+                break;
+            }
+        }
+        DevicePath::from_bytes_unchecked(slice::from_raw_parts(raw, length))
+    }
+
+    /// Takes the device path allocated from the pool by the firmware, `None` if it is null
+    ///
+    /// # Safety
+    ///
+    /// The same as for `from_ptr`, and the path must not be owned by anything else
+    pub unsafe fn from_pool(
+        boot_services: &BootServices,
+        raw: *mut u8,
+    ) -> Option<PoolBox<'_, DevicePath>> {
+        if raw.is_null() {
+            None
+        } else {
+            let device_path = DevicePath::from_ptr(raw) as *const DevicePath as *mut DevicePath;
+            Some(PoolBox::from_raw(boot_services, device_path))
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.inner
+    }
+
+    pub fn as_ptr(&self) -> *const u8 {
+        self.inner.as_ptr()
+    }
+
+    /// The path has only the end node
+    pub fn is_empty(&self) -> bool {
+        self.nodes().next().is_none()
+    }
+
+    /// The nodes up to the end of entire path node, which is not yielded,
+    /// the end of instance nodes are yielded
+    pub fn nodes(&self) -> Nodes<'_> {
+        Nodes { rest: &self.inner }
+    }
+}
+
+impl AsRef<DevicePath> for DevicePath {
+    fn as_ref(&self) -> &DevicePath {
+        self
+    }
+}
+
+impl ToOwned for DevicePath {
+    type Owned = DevicePathBuf;
+
+    fn to_owned(&self) -> Self::Owned {
+        DevicePathBuf {
+            inner: self.inner.to_vec(),
+        }
+    }
+}
+
+impl Protocol for DevicePath {
+    unsafe fn from_interface(raw: *mut ()) -> *mut Self {
+        DevicePath::from_ptr(raw as *const u8) as *const DevicePath as *mut DevicePath
+    }
+}
+
+impl HasGuid for DevicePath {
+    const GUID: Guid = Guid(
+        0x09576e91,
+        0x6d3f,
+        0x11d2,
+        [0x8e, 0x39, 0x00, 0xa0, 0xc9, 0x69, 0x72, 0x3b],
+    );
+}
+
+/// Owned device path, `DevicePathBuilder::with_vec` builds it in place
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct DevicePathBuf {
+    inner: Vec<u8>,
+}
+
+impl DevicePathBuf {
+    /// Checks the lengths of the nodes, the bytes after the end node are removed
+    pub fn from_vec(mut raw: Vec<u8>) -> Result<Self, DevicePathError> {
+        let length = DevicePath::new(&raw)?.inner.len();
+        raw.truncate(length);
+        Ok(DevicePathBuf { inner: raw })
+    }
+
+    pub fn as_device_path(&self) -> &DevicePath {
+        DevicePath::from_bytes_unchecked(&self.inner)
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.inner
+    }
+}
+
+impl Deref for DevicePathBuf {
+    type Target = DevicePath;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_device_path()
+    }
+}
+
+impl Borrow<DevicePath> for DevicePathBuf {
+    fn borrow(&self) -> &DevicePath {
+        self.as_device_path()
+    }
+}
+
+impl AsRef<DevicePath> for DevicePathBuf {
+    fn as_ref(&self) -> &DevicePath {
+        self.as_device_path()
+    }
+}
+
+impl<'a> From<&'a DevicePath> for DevicePathBuf {
+    fn from(device_path: &'a DevicePath) -> Self {
+        device_path.to_owned()
+    }
+}
+
+/// Single node of the device path, the header is included
+#[repr(transparent)]
+#[derive(Debug, Eq, PartialEq, Hash)]
+pub struct DevicePathNode {
+    inner: [u8],
+}
+
+impl DevicePathNode {
+    fn from_bytes_unchecked(raw: &[u8]) -> &Self {
+        unsafe { &*(raw as *const [u8] as *const DevicePathNode) }
+    }
+
+    pub fn device_type(&self) -> u8 {
+        self.inner[0]
+    }
+
+    pub fn sub_type(&self) -> u8 {
+        self.inner[1]
+    }
+
+    /// The data following the header
+    pub fn data(&self) -> &[u8] {
+        &self.inner[HEADER_SIZE..]
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.inner
+    }
+
+    pub fn is_end_instance(&self) -> bool {
+        self.device_type() == END_TYPE && self.sub_type() == END_INSTANCE_SUB_TYPE
+    }
+
+    /// Parses the node data, malformed nodes are `Unknown`
+    pub fn node(&self) -> Node<'_> {
+        Node::parse(self.device_type(), self.sub_type(), self.data())
+    }
+}
+
+pub struct Nodes<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Iterator for Nodes<'a> {
+    type Item = &'a DevicePathNode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let length = node_length(self.rest)?;
+        let (node, rest) = self.rest.split_at(length);
+        if node[0] == END_TYPE && node[1] == END_ENTIRE_SUB_TYPE {
+            self.rest = &[];
+            return None;
+        }
+        self.rest = rest;
+        Some(DevicePathNode::from_bytes_unchecked(node))
+    }
+}
@@ -0,0 +1,636 @@
+use common::Guid;
+use common::decode_ucs2;
+
+use core::convert::TryInto;
+use core::fmt;
+
+pub const HARDWARE_TYPE: u8 = 0x01;
+pub const ACPI_TYPE: u8 = 0x02;
+pub const MESSAGING_TYPE: u8 = 0x03;
+pub const MEDIA_TYPE: u8 = 0x04;
+pub const BBS_TYPE: u8 = 0x05;
+pub const END_TYPE: u8 = super::END_TYPE;
+
+// reads the node data, fails if it is too short
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, length: usize) -> Option<&'a [u8]> {
+        if self.data.len() < length {
+            return None;
+        }
+        let (bytes, rest) = self.data.split_at(length);
+        self.data = rest;
+        Some(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.bytes(N).and_then(|bytes| bytes.try_into().ok())
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.array().map(u8::from_le_bytes)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn guid(&mut self) -> Option<Guid> {
+        Some(Guid(self.u32()?, self.u16()?, self.u16()?, self.array()?))
+    }
+
+    // null terminated, the null is not included
+    fn ascii(&mut self) -> &'a [u8] {
+        let length = self
+            .data
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(self.data.len());
+        let (string, rest) = self.data.split_at(length);
+        self.data = rest.get(1..).unwrap_or(&[]);
+        string
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = self.data;
+        self.data = &[];
+        rest
+    }
+}
+
+/// UCS-2 string stored in the node, it might be unaligned, stops at the null
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Ucs2<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Ucs2<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Ucs2 { bytes }
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn units(&self) -> impl Iterator<Item = u16> + 'a {
+        self.bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .take_while(|&c| c != 0)
+    }
+
+    /// Invalid sequences are replaced with `U+FFFD`
+    pub fn chars(&self) -> impl Iterator<Item = char> + 'a {
+        decode_ucs2(self.units())
+    }
+}
+
+impl<'a> fmt::Display for Ucs2<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use core::fmt::Write;
+
+        for c in self.chars() {
+            f.write_char(c)?;
+        }
+        Ok(())
+    }
+}
+
+/// Typed device path node
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Node<'a> {
+    Hardware(Hardware<'a>),
+    Acpi(Acpi<'a>),
+    Messaging(Messaging<'a>),
+    Media(Media<'a>),
+    Bbs(Bbs<'a>),
+    End(End),
+    Unknown {
+        device_type: u8,
+        sub_type: u8,
+        data: &'a [u8],
+    },
+}
+
+impl<'a> Node<'a> {
+    /// Malformed nodes are `Unknown` of their type
+    pub fn parse(device_type: u8, sub_type: u8, data: &'a [u8]) -> Self {
+        let reader = &mut Reader { data };
+        match device_type {
+            HARDWARE_TYPE => Node::Hardware(
+                Hardware::parse(sub_type, reader).unwrap_or(Hardware::Unknown { sub_type, data }),
+            ),
+            ACPI_TYPE => Node::Acpi(
+                Acpi::parse(sub_type, reader).unwrap_or(Acpi::Unknown { sub_type, data }),
+            ),
+            MESSAGING_TYPE => Node::Messaging(
+                Messaging::parse(sub_type, reader).unwrap_or(Messaging::Unknown { sub_type, data }),
+            ),
+            MEDIA_TYPE => Node::Media(
+                Media::parse(sub_type, reader).unwrap_or(Media::Unknown { sub_type, data }),
+            ),
+            BBS_TYPE => {
+                Node::Bbs(Bbs::parse(sub_type, reader).unwrap_or(Bbs::Unknown { sub_type, data }))
+            }
+            END_TYPE => Node::End(match sub_type {
+                super::END_INSTANCE_SUB_TYPE => End::Instance,
+                _ => End::Entire,
+            }),
+            _ => Node::Unknown {
+                device_type,
+                sub_type,
+                data,
+            },
+        }
+    }
+
+    pub fn device_type(&self) -> u8 {
+        match *self {
+            Node::Hardware(_) => HARDWARE_TYPE,
+            Node::Acpi(_) => ACPI_TYPE,
+            Node::Messaging(_) => MESSAGING_TYPE,
+            Node::Media(_) => MEDIA_TYPE,
+            Node::Bbs(_) => BBS_TYPE,
+            Node::End(_) => END_TYPE,
+            Node::Unknown { device_type, .. } => device_type,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Hardware<'a> {
+    Pci {
+        function: u8,
+        device: u8,
+    },
+    PcCard {
+        function: u8,
+    },
+    MemoryMapped {
+        memory_type: u32,
+        start: u64,
+        end: u64,
+    },
+    Vendor {
+        guid: Guid,
+        data: &'a [u8],
+    },
+    Controller {
+        controller: u32,
+    },
+    Bmc {
+        interface_type: u8,
+        base_address: u64,
+    },
+    Unknown {
+        sub_type: u8,
+        data: &'a [u8],
+    },
+}
+
+impl<'a> Hardware<'a> {
+    fn parse(sub_type: u8, r: &mut Reader<'a>) -> Option<Self> {
+        Some(match sub_type {
+            0x01 => Hardware::Pci {
+                function: r.u8()?,
+                device: r.u8()?,
+            },
+            0x02 => Hardware::PcCard { function: r.u8()? },
+            0x03 => Hardware::MemoryMapped {
+                memory_type: r.u32()?,
+                start: r.u64()?,
+                end: r.u64()?,
+            },
+            0x04 => Hardware::Vendor {
+                guid: r.guid()?,
+                data: r.rest(),
+            },
+            0x05 => Hardware::Controller {
+                controller: r.u32()?,
+            },
+            0x06 => Hardware::Bmc {
+                interface_type: r.u8()?,
+                base_address: r.u64()?,
+            },
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Acpi<'a> {
+    /// The `hid` is the compressed EISA id, e.g. `PNP0A03`
+    Acpi {
+        hid: u32,
+        uid: u32,
+    },
+    /// The strings are used instead of the numbers if they are not empty
+    Expanded {
+        hid: u32,
+        uid: u32,
+        cid: u32,
+        hid_str: &'a [u8],
+        uid_str: &'a [u8],
+        cid_str: &'a [u8],
+    },
+    /// The list of 32 bit `_ADR` values, little endian
+    Adr {
+        adr: &'a [u8],
+    },
+    Nvdimm {
+        handle: u32,
+    },
+    Unknown {
+        sub_type: u8,
+        data: &'a [u8],
+    },
+}
+
+impl<'a> Acpi<'a> {
+    fn parse(sub_type: u8, r: &mut Reader<'a>) -> Option<Self> {
+        Some(match sub_type {
+            0x01 => Acpi::Acpi {
+                hid: r.u32()?,
+                uid: r.u32()?,
+            },
+            0x02 => Acpi::Expanded {
+                hid: r.u32()?,
+                uid: r.u32()?,
+                cid: r.u32()?,
+                hid_str: r.ascii(),
+                uid_str: r.ascii(),
+                cid_str: r.ascii(),
+            },
+            0x03 => {
+                let adr = r.rest();
+                if adr.is_empty() || !adr.len().is_multiple_of(4) {
+                    return None;
+                }
+                Acpi::Adr { adr }
+            }
+            0x04 => Acpi::Nvdimm { handle: r.u32()? },
+            _ => return None,
+        })
+    }
+}
+
+/// Composes the EISA id of the `PNP` devices
+pub const fn eisa_pnp_id(id: u16) -> u32 {
+    ((id as u32) << 16) | 0x41d0
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Messaging<'a> {
+    Atapi {
+        primary_secondary: u8,
+        slave_master: u8,
+        lun: u16,
+    },
+    Scsi {
+        target: u16,
+        lun: u16,
+    },
+    FibreChannel {
+        world_wide_name: u64,
+        lun: u64,
+    },
+    Usb {
+        parent_port: u8,
+        interface: u8,
+    },
+    Vendor {
+        guid: Guid,
+        data: &'a [u8],
+    },
+    MacAddress {
+        address: [u8; 32],
+        interface_type: u8,
+    },
+    /// The gateway and the subnet mask are zero in the older nodes without them
+    Ipv4 {
+        local_address: [u8; 4],
+        remote_address: [u8; 4],
+        local_port: u16,
+        remote_port: u16,
+        protocol: u16,
+        static_address: bool,
+        gateway_address: [u8; 4],
+        subnet_mask: [u8; 4],
+    },
+    /// The prefix length and the gateway are zero in the older nodes without them
+    Ipv6 {
+        local_address: [u8; 16],
+        remote_address: [u8; 16],
+        local_port: u16,
+        remote_port: u16,
+        protocol: u16,
+        address_origin: u8,
+        prefix_length: u8,
+        gateway_address: [u8; 16],
+    },
+    UsbClass {
+        vendor_id: u16,
+        product_id: u16,
+        class: u8,
+        subclass: u8,
+        protocol: u8,
+    },
+    UsbWwid {
+        interface: u16,
+        vendor_id: u16,
+        product_id: u16,
+        serial_number: Ucs2<'a>,
+    },
+    DeviceLogicalUnit {
+        lun: u8,
+    },
+    Sata {
+        hba_port: u16,
+        port_multiplier_port: u16,
+        lun: u16,
+    },
+    Vlan {
+        id: u16,
+    },
+    Nvme {
+        namespace_id: u32,
+        eui64: [u8; 8],
+    },
+    Uri {
+        uri: &'a [u8],
+    },
+    Ufs {
+        target: u8,
+        lun: u8,
+    },
+    Sd {
+        slot: u8,
+    },
+    Emmc {
+        slot: u8,
+    },
+    Unknown {
+        sub_type: u8,
+        data: &'a [u8],
+    },
+}
+
+impl<'a> Messaging<'a> {
+    fn parse(sub_type: u8, r: &mut Reader<'a>) -> Option<Self> {
+        Some(match sub_type {
+            0x01 => Messaging::Atapi {
+                primary_secondary: r.u8()?,
+                slave_master: r.u8()?,
+                lun: r.u16()?,
+            },
+            0x02 => Messaging::Scsi {
+                target: r.u16()?,
+                lun: r.u16()?,
+            },
+            0x03 => {
+                let _reserved = r.u32()?;
+                Messaging::FibreChannel {
+                    world_wide_name: r.u64()?,
+                    lun: r.u64()?,
+                }
+            }
+            0x05 => Messaging::Usb {
+                parent_port: r.u8()?,
+                interface: r.u8()?,
+            },
+            0x0a => Messaging::Vendor {
+                guid: r.guid()?,
+                data: r.rest(),
+            },
+            0x0b => Messaging::MacAddress {
+                address: r.array()?,
+                interface_type: r.u8()?,
+            },
+            0x0c => Messaging::Ipv4 {
+                local_address: r.array()?,
+                remote_address: r.array()?,
+                local_port: r.u16()?,
+                remote_port: r.u16()?,
+                protocol: r.u16()?,
+                static_address: r.u8()? != 0,
+                gateway_address: r.array().unwrap_or_default(),
+                subnet_mask: r.array().unwrap_or_default(),
+            },
+            0x0d => Messaging::Ipv6 {
+                local_address: r.array()?,
+                remote_address: r.array()?,
+                local_port: r.u16()?,
+                remote_port: r.u16()?,
+                protocol: r.u16()?,
+                address_origin: r.u8()?,
+                prefix_length: r.u8().unwrap_or_default(),
+                gateway_address: r.array().unwrap_or_default(),
+            },
+            0x0f => Messaging::UsbClass {
+                vendor_id: r.u16()?,
+                product_id: r.u16()?,
+                class: r.u8()?,
+                subclass: r.u8()?,
+                protocol: r.u8()?,
+            },
+            0x10 => Messaging::UsbWwid {
+                interface: r.u16()?,
+                vendor_id: r.u16()?,
+                product_id: r.u16()?,
+                serial_number: Ucs2::new(r.rest()),
+            },
+            0x11 => Messaging::DeviceLogicalUnit { lun: r.u8()? },
+            0x12 => Messaging::Sata {
+                hba_port: r.u16()?,
+                port_multiplier_port: r.u16()?,
+                lun: r.u16()?,
+            },
+            0x14 => Messaging::Vlan { id: r.u16()? },
+            0x17 => Messaging::Nvme {
+                namespace_id: r.u32()?,
+                eui64: r.array()?,
+            },
+            0x18 => Messaging::Uri { uri: r.rest() },
+            0x19 => Messaging::Ufs {
+                target: r.u8()?,
+                lun: r.u8()?,
+            },
+            0x1a => Messaging::Sd { slot: r.u8()? },
+            0x1d => Messaging::Emmc { slot: r.u8()? },
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PartitionFormat {
+    Mbr,
+    Gpt,
+    Unknown(u8),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PartitionSignature {
+    None,
+    Mbr(u32),
+    Guid(Guid),
+    Unknown(u8, [u8; 16]),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Media<'a> {
+    /// The start and the size are in logical blocks
+    HardDrive {
+        partition_number: u32,
+        partition_start: u64,
+        partition_size: u64,
+        signature: PartitionSignature,
+        format: PartitionFormat,
+    },
+    CdRom {
+        boot_entry: u32,
+        partition_start: u64,
+        partition_size: u64,
+    },
+    Vendor {
+        guid: Guid,
+        data: &'a [u8],
+    },
+    FilePath {
+        path: Ucs2<'a>,
+    },
+    Protocol {
+        guid: Guid,
+    },
+    FirmwareFile {
+        name: Guid,
+    },
+    FirmwareVolume {
+        name: Guid,
+    },
+    RelativeOffsetRange {
+        start: u64,
+        end: u64,
+    },
+    RamDisk {
+        start: u64,
+        end: u64,
+        disk_type: Guid,
+        instance: u16,
+    },
+    Unknown {
+        sub_type: u8,
+        data: &'a [u8],
+    },
+}
+
+impl<'a> Media<'a> {
+    fn parse(sub_type: u8, r: &mut Reader<'a>) -> Option<Self> {
+        Some(match sub_type {
+            0x01 => {
+                let partition_number = r.u32()?;
+                let partition_start = r.u64()?;
+                let partition_size = r.u64()?;
+                let signature: [u8; 16] = r.array()?;
+                let format = match r.u8()? {
+                    0x01 => PartitionFormat::Mbr,
+                    0x02 => PartitionFormat::Gpt,
+                    format => PartitionFormat::Unknown(format),
+                };
+                let signature = match r.u8()? {
+                    0x00 => PartitionSignature::None,
+                    0x01 => PartitionSignature::Mbr(u32::from_le_bytes([
+                        signature[0],
+                        signature[1],
+                        signature[2],
+                        signature[3],
+                    ])),
+                    0x02 => PartitionSignature::Guid(Reader { data: &signature }.guid()?),
+                    signature_type => PartitionSignature::Unknown(signature_type, signature),
+                };
+                Media::HardDrive {
+                    partition_number,
+                    partition_start,
+                    partition_size,
+                    signature,
+                    format,
+                }
+            }
+            0x02 => Media::CdRom {
+                boot_entry: r.u32()?,
+                partition_start: r.u64()?,
+                partition_size: r.u64()?,
+            },
+            0x03 => Media::Vendor {
+                guid: r.guid()?,
+                data: r.rest(),
+            },
+            0x04 => Media::FilePath {
+                path: Ucs2::new(r.rest()),
+            },
+            0x05 => Media::Protocol { guid: r.guid()? },
+            0x06 => Media::FirmwareFile { name: r.guid()? },
+            0x07 => Media::FirmwareVolume { name: r.guid()? },
+            0x08 => {
+                let _reserved = r.u32()?;
+                Media::RelativeOffsetRange {
+                    start: r.u64()?,
+                    end: r.u64()?,
+                }
+            }
+            0x09 => Media::RamDisk {
+                start: r.u64()?,
+                end: r.u64()?,
+                disk_type: r.guid()?,
+                instance: r.u16()?,
+            },
+            _ => return None,
+        })
+    }
+}
+
+/// BIOS Boot Specification device
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Bbs<'a> {
+    Bbs {
+        device_type: u16,
+        status_flag: u16,
+        description: &'a [u8],
+    },
+    Unknown {
+        sub_type: u8,
+        data: &'a [u8],
+    },
+}
+
+impl<'a> Bbs<'a> {
+    fn parse(sub_type: u8, r: &mut Reader<'a>) -> Option<Self> {
+        Some(match sub_type {
+            0x01 => Bbs::Bbs {
+                device_type: r.u16()?,
+                status_flag: r.u16()?,
+                description: r.ascii(),
+            },
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum End {
+    /// Separates the instances of the multi-instance path
+    Instance,
+    Entire,
+}
@@ -0,0 +1,1201 @@
+use common::Guid;
+
+use super::node::eisa_pnp_id;
+use super::node::Acpi;
+use super::node::Bbs;
+use super::node::End;
+use super::node::Hardware;
+use super::node::Media;
+use super::node::Messaging;
+use super::node::Node;
+use super::node::PartitionFormat;
+use super::node::PartitionSignature;
+use super::node::Ucs2;
+use super::node::ACPI_TYPE;
+use super::node::BBS_TYPE;
+use super::node::HARDWARE_TYPE;
+use super::node::MEDIA_TYPE;
+use super::node::MESSAGING_TYPE;
+use super::DevicePath;
+use super::DevicePathBuf;
+use super::DevicePathBuilder;
+use super::DevicePathError;
+use super::DevicePathNode;
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::str::FromStr;
+
+use alloc::vec::Vec;
+
+// the text form prints the numbers in upper case hex, like the reference implementation
+struct Hex<'a>(&'a [u8]);
+
+impl<'a> fmt::Display for Hex<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+struct Ascii<'a>(&'a [u8]);
+
+impl<'a> fmt::Display for Ascii<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use core::fmt::Write;
+
+        for &byte in self.0 {
+            f.write_char(byte as char)?;
+        }
+        Ok(())
+    }
+}
+
+struct GuidText<'a>(&'a Guid);
+
+impl<'a> fmt::Display for GuidText<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Guid(a, b, c, ref d) = *self.0;
+        write!(
+            f,
+            "{:08X}-{:04X}-{:04X}-{}-{}",
+            a,
+            b,
+            c,
+            Hex(&d[..2]),
+            Hex(&d[2..])
+        )
+    }
+}
+
+// the compressed EISA id, three letters and the product number, e.g. `PNP0A03`
+struct EisaId(u32);
+
+impl EisaId {
+    fn letters(&self) -> Option<[char; 3]> {
+        let letter = |shift: u32| match (self.0 >> shift) & 0x1f {
+            code @ 1..=26 => Some((b'A' + code as u8 - 1) as char),
+            _ => None,
+        };
+        Some([letter(10)?, letter(5)?, letter(0)?])
+    }
+}
+
+impl fmt::Display for EisaId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.letters() {
+            Some([a, b, c]) if self.0 & 0x8000 == 0 => {
+                write!(f, "{}{}{}{:04X}", a, b, c, self.0 >> 16)
+            }
+            _ => write!(f, "{:#X}", self.0),
+        }
+    }
+}
+
+struct Ipv4Text<'a>(&'a [u8; 4]);
+
+impl<'a> fmt::Display for Ipv4Text<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [a, b, c, d] = *self.0;
+        write!(f, "{}.{}.{}.{}", a, b, c, d)
+    }
+}
+
+struct Ipv6Text<'a>(&'a [u8; 16]);
+
+impl<'a> fmt::Display for Ipv6Text<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, pair) in self.0.chunks_exact(2).enumerate() {
+            if index != 0 {
+                f.write_str(":")?;
+            }
+            write!(f, "{:X}", u16::from_be_bytes([pair[0], pair[1]]))?;
+        }
+        Ok(())
+    }
+}
+
+fn write_unknown(
+    f: &mut fmt::Formatter,
+    device_type: u8,
+    sub_type: u8,
+    data: &[u8],
+) -> fmt::Result {
+    write!(f, "Path({},{},{})", device_type, sub_type, Hex(data))
+}
+
+fn write_vendor(f: &mut fmt::Formatter, name: &str, guid: &Guid, data: &[u8]) -> fmt::Result {
+    write!(f, "{}({}", name, GuidText(guid))?;
+    if !data.is_empty() {
+        write!(f, ",{}", Hex(data))?;
+    }
+    f.write_str(")")
+}
+
+fn protocol_name(protocol: u16) -> Option<&'static str> {
+    match protocol {
+        6 => Some("TCP"),
+        17 => Some("UDP"),
+        _ => None,
+    }
+}
+
+struct ProtocolText(u16);
+
+impl fmt::Display for ProtocolText {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match protocol_name(self.0) {
+            Some(name) => f.write_str(name),
+            None => write!(f, "{:#X}", self.0),
+        }
+    }
+}
+
+impl<'a> fmt::Display for Hardware<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Hardware::Pci { function, device } => write!(f, "Pci({:#X},{:#X})", device, function),
+            Hardware::PcCard { function } => write!(f, "PcCard({:#X})", function),
+            Hardware::MemoryMapped {
+                memory_type,
+                start,
+                end,
+            } => write!(
+                f,
+                "MemoryMapped({:#X},{:#X},{:#X})",
+                memory_type, start, end
+            ),
+            Hardware::Vendor { ref guid, data } => write_vendor(f, "VenHw", guid, data),
+            Hardware::Controller { controller } => write!(f, "Ctrl({:#X})", controller),
+            Hardware::Bmc {
+                interface_type,
+                base_address,
+            } => write!(f, "BMC({:#X},{:#X})", interface_type, base_address),
+            Hardware::Unknown { sub_type, data } => write_unknown(f, HARDWARE_TYPE, sub_type, data),
+        }
+    }
+}
+
+// the well known devices have the short form
+const ACPI_NAMES: [(&str, u16); 6] = [
+    ("PciRoot", 0x0a03),
+    ("PcieRoot", 0x0a08),
+    ("Floppy", 0x0604),
+    ("Keyboard", 0x0301),
+    ("Serial", 0x0501),
+    ("ParallelPort", 0x0401),
+];
+
+impl<'a> fmt::Display for Acpi<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Acpi::Acpi { hid, uid } => {
+                match ACPI_NAMES.iter().find(|&&(_, id)| eisa_pnp_id(id) == hid) {
+                    Some(&(name, _)) => write!(f, "{}({:#X})", name, uid),
+                    None => write!(f, "Acpi({},{:#X})", EisaId(hid), uid),
+                }
+            }
+            Acpi::Expanded {
+                hid,
+                uid,
+                cid,
+                hid_str,
+                uid_str,
+                cid_str,
+            } => write!(
+                f,
+                "AcpiEx({},{},{:#X},{},{},{})",
+                EisaId(hid),
+                EisaId(cid),
+                uid,
+                Ascii(hid_str),
+                Ascii(cid_str),
+                Ascii(uid_str)
+            ),
+            Acpi::Adr { adr } => {
+                f.write_str("AcpiAdr(")?;
+                for (index, value) in adr.chunks_exact(4).enumerate() {
+                    if index != 0 {
+                        f.write_str(",")?;
+                    }
+                    let value = u32::from_le_bytes([value[0], value[1], value[2], value[3]]);
+                    write!(f, "{:#X}", value)?;
+                }
+                f.write_str(")")
+            }
+            Acpi::Nvdimm { handle } => write!(f, "Nvdimm({:#X})", handle),
+            Acpi::Unknown { sub_type, data } => write_unknown(f, ACPI_TYPE, sub_type, data),
+        }
+    }
+}
+
+impl<'a> fmt::Display for Messaging<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Messaging::Atapi {
+                primary_secondary,
+                slave_master,
+                lun,
+            } => {
+                match primary_secondary {
+                    0 => f.write_str("Ata(Primary,")?,
+                    1 => f.write_str("Ata(Secondary,")?,
+                    primary_secondary => write!(f, "Ata({:#X},", primary_secondary)?,
+                }
+                match slave_master {
+                    0 => f.write_str("Master,")?,
+                    1 => f.write_str("Slave,")?,
+                    slave_master => write!(f, "{:#X},", slave_master)?,
+                }
+                write!(f, "{:#X})", lun)
+            }
+            Messaging::Scsi { target, lun } => write!(f, "Scsi({:#X},{:#X})", target, lun),
+            Messaging::FibreChannel {
+                world_wide_name,
+                lun,
+            } => write!(f, "Fibre({:#X},{:#X})", world_wide_name, lun),
+            Messaging::Usb {
+                parent_port,
+                interface,
+            } => write!(f, "USB({:#X},{:#X})", parent_port, interface),
+            Messaging::Vendor { ref guid, data } => write_vendor(f, "VenMsg", guid, data),
+            Messaging::MacAddress {
+                ref address,
+                interface_type,
+            } => {
+                // ethernet and the experimental ethernet have 6 bytes addresses
+                let length = if interface_type <= 1 { 6 } else { 32 };
+                write!(f, "MAC({},{:#X})", Hex(&address[..length]), interface_type)
+            }
+            Messaging::Ipv4 {
+                ref local_address,
+                ref remote_address,
+                protocol,
+                static_address,
+                ref gateway_address,
+                ref subnet_mask,
+                ..
+            } => write!(
+                f,
+                "IPv4({},{},{},{},{},{})",
+                Ipv4Text(remote_address),
+                ProtocolText(protocol),
+                if static_address { "Static" } else { "DHCP" },
+                Ipv4Text(local_address),
+                Ipv4Text(gateway_address),
+                Ipv4Text(subnet_mask)
+            ),
+            Messaging::Ipv6 {
+                ref local_address,
+                ref remote_address,
+                protocol,
+                address_origin,
+                prefix_length,
+                ref gateway_address,
+                ..
+            } => {
+                let address_origin = match address_origin {
+                    0 => "Static",
+                    1 => "StatelessAutoConfigure",
+                    _ => "StatefulAutoConfigure",
+                };
+                write!(
+                    f,
+                    "IPv6({},{},{},{},{:#X},{})",
+                    Ipv6Text(remote_address),
+                    ProtocolText(protocol),
+                    address_origin,
+                    Ipv6Text(local_address),
+                    prefix_length,
+                    Ipv6Text(gateway_address)
+                )
+            }
+            Messaging::UsbClass {
+                vendor_id,
+                product_id,
+                class,
+                subclass,
+                protocol,
+            } => write!(
+                f,
+                "UsbClass({:#X},{:#X},{:#X},{:#X},{:#X})",
+                vendor_id, product_id, class, subclass, protocol
+            ),
+            Messaging::UsbWwid {
+                interface,
+                vendor_id,
+                product_id,
+                serial_number,
+            } => write!(
+                f,
+                "UsbWwid({:#X},{:#X},{:#X},\"{}\")",
+                vendor_id, product_id, interface, serial_number
+            ),
+            Messaging::DeviceLogicalUnit { lun } => write!(f, "Unit({:#X})", lun),
+            Messaging::Sata {
+                hba_port,
+                port_multiplier_port,
+                lun,
+            } => write!(
+                f,
+                "Sata({:#X},{:#X},{:#X})",
+                hba_port, port_multiplier_port, lun
+            ),
+            Messaging::Vlan { id } => write!(f, "Vlan({})", id),
+            Messaging::Nvme {
+                namespace_id,
+                ref eui64,
+            } => {
+                write!(f, "NVMe({:#X},", namespace_id)?;
+                // printed from the most significant byte
+                for (index, byte) in eui64.iter().rev().enumerate() {
+                    if index != 0 {
+                        f.write_str("-")?;
+                    }
+                    write!(f, "{:02X}", byte)?;
+                }
+                f.write_str(")")
+            }
+            Messaging::Uri { uri } => write!(f, "Uri({})", Ascii(uri)),
+            Messaging::Ufs { target, lun } => write!(f, "UFS({:#X},{:#X})", target, lun),
+            Messaging::Sd { slot } => write!(f, "SD({:#X})", slot),
+            Messaging::Emmc { slot } => write!(f, "eMMC({:#X})", slot),
+            Messaging::Unknown { sub_type, data } => {
+                write_unknown(f, MESSAGING_TYPE, sub_type, data)
+            }
+        }
+    }
+}
+
+impl<'a> fmt::Display for Media<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Media::HardDrive {
+                partition_number,
+                partition_start,
+                partition_size,
+                signature,
+                format,
+            } => {
+                write!(f, "HD({},", partition_number)?;
+                match (format, signature) {
+                    (PartitionFormat::Mbr, PartitionSignature::Mbr(signature)) => {
+                        write!(f, "MBR,{:#010X}", signature)?
+                    }
+                    (PartitionFormat::Gpt, PartitionSignature::Guid(ref guid)) => {
+                        write!(f, "GPT,{}", GuidText(guid))?
+                    }
+                    // any other pair keeps both numbers, so it is encoded back as it was
+                    (format, signature) => {
+                        let format = match format {
+                            PartitionFormat::Mbr => 0x01,
+                            PartitionFormat::Gpt => 0x02,
+                            PartitionFormat::Unknown(format) => format,
+                        };
+                        write!(f, "{:#X},", format)?;
+                        match signature {
+                            PartitionSignature::None => f.write_str("0x0,0")?,
+                            PartitionSignature::Mbr(signature) => {
+                                write!(f, "0x1,{:#010X}", signature)?
+                            }
+                            PartitionSignature::Guid(ref guid) => {
+                                write!(f, "0x2,{}", GuidText(guid))?
+                            }
+                            PartitionSignature::Unknown(signature_type, ref signature) => {
+                                write!(f, "{:#X},{}", signature_type, Hex(signature))?
+                            }
+                        }
+                    }
+                }
+                write!(f, ",{:#X},{:#X})", partition_start, partition_size)
+            }
+            Media::CdRom {
+                boot_entry,
+                partition_start,
+                partition_size,
+            } => write!(
+                f,
+                "CDROM({:#X},{:#X},{:#X})",
+                boot_entry, partition_start, partition_size
+            ),
+            Media::Vendor { ref guid, data } => write_vendor(f, "VenMedia", guid, data),
+            Media::FilePath { path } => path.fmt(f),
+            Media::Protocol { ref guid } => write!(f, "Media({})", GuidText(guid)),
+            Media::FirmwareFile { ref name } => write!(f, "FvFile({})", GuidText(name)),
+            Media::FirmwareVolume { ref name } => write!(f, "Fv({})", GuidText(name)),
+            Media::RelativeOffsetRange { start, end } => {
+                write!(f, "Offset({:#X},{:#X})", start, end)
+            }
+            Media::RamDisk {
+                start,
+                end,
+                ref disk_type,
+                instance,
+            } => write!(
+                f,
+                "RamDisk({:#X},{:#X},{},{})",
+                start,
+                end,
+                instance,
+                GuidText(disk_type)
+            ),
+            Media::Unknown { sub_type, data } => write_unknown(f, MEDIA_TYPE, sub_type, data),
+        }
+    }
+}
+
+impl<'a> fmt::Display for Bbs<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Bbs::Bbs {
+                device_type,
+                status_flag,
+                description,
+            } => write!(
+                f,
+                "BBS({:#X},\"{}\",{:#X})",
+                device_type,
+                Ascii(description),
+                status_flag
+            ),
+            Bbs::Unknown { sub_type, data } => write_unknown(f, BBS_TYPE, sub_type, data),
+        }
+    }
+}
+
+/// The end of instance is `,`, the end of entire path is empty
+impl<'a> fmt::Display for Node<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Node::Hardware(ref node) => node.fmt(f),
+            Node::Acpi(ref node) => node.fmt(f),
+            Node::Messaging(ref node) => node.fmt(f),
+            Node::Media(ref node) => node.fmt(f),
+            Node::Bbs(ref node) => node.fmt(f),
+            Node::End(End::Instance) => f.write_str(","),
+            Node::End(End::Entire) => Ok(()),
+            Node::Unknown {
+                device_type,
+                sub_type,
+                data,
+            } => write_unknown(f, device_type, sub_type, data),
+        }
+    }
+}
+
+impl fmt::Display for DevicePathNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.node().fmt(f)
+    }
+}
+
+/// The nodes are separated by `/`, the instances by `,`
+impl fmt::Display for DevicePath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut first = true;
+        for node in self.nodes() {
+            if node.is_end_instance() {
+                f.write_str(",")?;
+                first = true;
+            } else {
+                if !first {
+                    f.write_str("/")?;
+                }
+                node.fmt(f)?;
+                first = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DevicePathBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_device_path().fmt(f)
+    }
+}
+
+// the arguments of the node text, the missing and the empty numbers are zero
+struct Args<'s> {
+    list: Vec<&'s str>,
+}
+
+impl<'s> Args<'s> {
+    fn new(text: &'s str) -> Self {
+        let mut list = Vec::new();
+        let mut quoted = false;
+        let mut start = 0;
+        for (index, c) in text.char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                ',' if !quoted => {
+                    list.push(&text[start..index]);
+                    start = index + 1;
+                }
+                _ => (),
+            }
+        }
+        if !text.trim().is_empty() {
+            list.push(&text[start..]);
+        }
+        let list = list
+            .into_iter()
+            .map(|arg| {
+                let arg = arg.trim();
+                if arg.len() >= 2 && arg.starts_with('"') && arg.ends_with('"') {
+                    &arg[1..(arg.len() - 1)]
+                } else {
+                    arg
+                }
+            })
+            .collect();
+        Args { list }
+    }
+
+    fn str(&self, index: usize) -> &'s str {
+        self.list.get(index).cloned().unwrap_or("")
+    }
+
+    fn number<T>(&self, index: usize) -> Option<T>
+    where
+        T: TryFrom<u64>,
+    {
+        let arg = self.str(index);
+        let value = if arg.is_empty() {
+            0
+        } else if arg.starts_with("0x") || arg.starts_with("0X") {
+            u64::from_str_radix(&arg[2..], 16).ok()?
+        } else {
+            arg.parse().ok()?
+        };
+        T::try_from(value).ok()
+    }
+
+    fn hex(&self, index: usize) -> Option<Vec<u8>> {
+        let arg = self.str(index).as_bytes();
+        if !arg.len().is_multiple_of(2) {
+            return None;
+        }
+        arg.chunks_exact(2)
+            .map(|pair| {
+                let pair = core::str::from_utf8(pair).ok()?;
+                u8::from_str_radix(pair, 16).ok()
+            })
+            .collect()
+    }
+
+    fn guid(&self, index: usize) -> Option<Guid> {
+        let mut parts = self.str(index).split('-');
+        let mut part = |length: usize| {
+            parts
+                .next()
+                .filter(|part| part.len() == length)
+                .and_then(|part| u64::from_str_radix(part, 16).ok())
+        };
+        let (a, b, c, d, e) = (part(8)?, part(4)?, part(4)?, part(4)?, part(12)?);
+        if parts.next().is_some() {
+            return None;
+        }
+        let mut tail = [0; 8];
+        tail[..2].copy_from_slice(&(d as u16).to_be_bytes());
+        tail[2..].copy_from_slice(&e.to_be_bytes()[2..]);
+        Some(Guid(a as u32, b as u16, c as u16, tail))
+    }
+
+    fn eisa_id(&self, index: usize) -> Option<u32> {
+        let arg = self.str(index).as_bytes();
+        match arg {
+            [a, b, c, _, _, _, _] if arg[..3].iter().all(u8::is_ascii_uppercase) => {
+                let letter = |c: u8| (c - b'A' + 1) as u32;
+                let product = core::str::from_utf8(&arg[3..]).ok()?;
+                let product = u16::from_str_radix(product, 16).ok()?;
+                Some(((product as u32) << 16) | (letter(*a) << 10) | (letter(*b) << 5) | letter(*c))
+            }
+            _ => self.number(index),
+        }
+    }
+
+    fn ipv4(&self, index: usize) -> Option<[u8; 4]> {
+        let arg = self.str(index);
+        let mut address = [0; 4];
+        if !arg.is_empty() {
+            let mut parts = arg.split('.');
+            for byte in address.iter_mut() {
+                *byte = parts.next()?.parse().ok()?;
+            }
+            if parts.next().is_some() {
+                return None;
+            }
+        }
+        Some(address)
+    }
+
+    fn ipv6(&self, index: usize) -> Option<[u8; 16]> {
+        let groups = |text: &str| -> Option<Vec<u16>> {
+            if text.is_empty() {
+                return Some(Vec::new());
+            }
+            text.split(':')
+                .map(|group| u16::from_str_radix(group, 16).ok())
+                .collect()
+        };
+        let arg = self.str(index);
+        let mut address = [0; 16];
+        if arg.is_empty() {
+            return Some(address);
+        }
+        let (head, tail) = match arg.find("::") {
+            Some(position) => {
+                let head = groups(&arg[..position])?;
+                let tail = groups(&arg[(position + 2)..])?;
+                if head.len() + tail.len() > 7 {
+                    return None;
+                }
+                (head, tail)
+            }
+            None => {
+                let head = groups(arg)?;
+                if head.len() != 8 {
+                    return None;
+                }
+                (head, Vec::new())
+            }
+        };
+        let tail_start = 8 - tail.len();
+        for (index, group) in head.iter().enumerate() {
+            address[(index * 2)..(index * 2 + 2)].copy_from_slice(&group.to_be_bytes());
+        }
+        for (index, group) in tail.iter().enumerate() {
+            let index = tail_start + index;
+            address[(index * 2)..(index * 2 + 2)].copy_from_slice(&group.to_be_bytes());
+        }
+        Some(address)
+    }
+
+    fn protocol(&self, index: usize) -> Option<u16> {
+        match self.str(index) {
+            "TCP" => Some(6),
+            "UDP" => Some(17),
+            _ => self.number(index),
+        }
+    }
+}
+
+fn ucs2_bytes(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+// pushes the node parsed from the `text`, `None` if it is not recognized
+fn push_node(builder: &mut DevicePathBuilder, text: &str) -> Option<()> {
+    let text = text.trim();
+    let (name, raw_args) = match text.find('(') {
+        Some(open) if text.ends_with(')') => (&text[..open], &text[(open + 1)..(text.len() - 1)]),
+        _ => {
+            let path = ucs2_bytes(text);
+            let node = Node::Media(Media::FilePath {
+                path: Ucs2::new(&path),
+            });
+            return builder.push(&node).ok().map(|_| ());
+        }
+    };
+    let a = Args::new(raw_args);
+
+    let bytes;
+    let node = match name {
+        "Pci" => Node::Hardware(Hardware::Pci {
+            function: a.number(1)?,
+            device: a.number(0)?,
+        }),
+        "PcCard" => Node::Hardware(Hardware::PcCard {
+            function: a.number(0)?,
+        }),
+        "MemoryMapped" => Node::Hardware(Hardware::MemoryMapped {
+            memory_type: a.number(0)?,
+            start: a.number(1)?,
+            end: a.number(2)?,
+        }),
+        "VenHw" => {
+            bytes = a.hex(1)?;
+            Node::Hardware(Hardware::Vendor {
+                guid: a.guid(0)?,
+                data: &bytes,
+            })
+        }
+        "Ctrl" => Node::Hardware(Hardware::Controller {
+            controller: a.number(0)?,
+        }),
+        "BMC" => Node::Hardware(Hardware::Bmc {
+            interface_type: a.number(0)?,
+            base_address: a.number(1)?,
+        }),
+        "Acpi" => Node::Acpi(Acpi::Acpi {
+            hid: a.eisa_id(0)?,
+            uid: a.number(1)?,
+        }),
+        "AcpiEx" => Node::Acpi(Acpi::Expanded {
+            hid: a.eisa_id(0)?,
+            cid: a.eisa_id(1)?,
+            uid: a.number(2)?,
+            hid_str: a.str(3).as_bytes(),
+            cid_str: a.str(4).as_bytes(),
+            uid_str: a.str(5).as_bytes(),
+        }),
+        "AcpiAdr" => {
+            bytes = (0..a.list.len())
+                .map(|index| a.number::<u32>(index).map(u32::to_le_bytes))
+                .collect::<Option<Vec<_>>>()?
+                .concat();
+            Node::Acpi(Acpi::Adr { adr: &bytes })
+        }
+        "Nvdimm" => Node::Acpi(Acpi::Nvdimm {
+            handle: a.number(0)?,
+        }),
+        "Ata" => Node::Messaging(Messaging::Atapi {
+            primary_secondary: match a.str(0) {
+                "Primary" => 0,
+                "Secondary" => 1,
+                _ => a.number(0)?,
+            },
+            slave_master: match a.str(1) {
+                "Master" => 0,
+                "Slave" => 1,
+                _ => a.number(1)?,
+            },
+            lun: a.number(2)?,
+        }),
+        "Scsi" => Node::Messaging(Messaging::Scsi {
+            target: a.number(0)?,
+            lun: a.number(1)?,
+        }),
+        "Fibre" => Node::Messaging(Messaging::FibreChannel {
+            world_wide_name: a.number(0)?,
+            lun: a.number(1)?,
+        }),
+        "USB" => Node::Messaging(Messaging::Usb {
+            parent_port: a.number(0)?,
+            interface: a.number(1)?,
+        }),
+        "VenMsg" => {
+            bytes = a.hex(1)?;
+            Node::Messaging(Messaging::Vendor {
+                guid: a.guid(0)?,
+                data: &bytes,
+            })
+        }
+        "MAC" => {
+            let hex = a.hex(0)?;
+            let mut address = [0; 32];
+            address.get_mut(..hex.len())?.copy_from_slice(&hex);
+            Node::Messaging(Messaging::MacAddress {
+                address,
+                interface_type: a.number(1)?,
+            })
+        }
+        "IPv4" => Node::Messaging(Messaging::Ipv4 {
+            remote_address: a.ipv4(0)?,
+            protocol: a.protocol(1)?,
+            static_address: match a.str(2) {
+                "Static" => true,
+                "DHCP" | "" => false,
+                _ => return None,
+            },
+            local_address: a.ipv4(3)?,
+            gateway_address: a.ipv4(4)?,
+            subnet_mask: a.ipv4(5)?,
+            local_port: 0,
+            remote_port: 0,
+        }),
+        "IPv6" => Node::Messaging(Messaging::Ipv6 {
+            remote_address: a.ipv6(0)?,
+            protocol: a.protocol(1)?,
+            address_origin: match a.str(2) {
+                "Static" | "" => 0,
+                "StatelessAutoConfigure" => 1,
+                "StatefulAutoConfigure" => 2,
+                _ => return None,
+            },
+            local_address: a.ipv6(3)?,
+            prefix_length: a.number(4)?,
+            gateway_address: a.ipv6(5)?,
+            local_port: 0,
+            remote_port: 0,
+        }),
+        "UsbClass" => Node::Messaging(Messaging::UsbClass {
+            vendor_id: a.number(0)?,
+            product_id: a.number(1)?,
+            class: a.number(2)?,
+            subclass: a.number(3)?,
+            protocol: a.number(4)?,
+        }),
+        "UsbWwid" => {
+            bytes = ucs2_bytes(a.str(3));
+            Node::Messaging(Messaging::UsbWwid {
+                vendor_id: a.number(0)?,
+                product_id: a.number(1)?,
+                interface: a.number(2)?,
+                serial_number: Ucs2::new(&bytes),
+            })
+        }
+        "Unit" => Node::Messaging(Messaging::DeviceLogicalUnit { lun: a.number(0)? }),
+        "Sata" => Node::Messaging(Messaging::Sata {
+            hba_port: a.number(0)?,
+            port_multiplier_port: a.number(1)?,
+            lun: a.number(2)?,
+        }),
+        "Vlan" => Node::Messaging(Messaging::Vlan { id: a.number(0)? }),
+        "NVMe" => {
+            let mut eui64 = [0; 8];
+            let mut parts = a.str(1).split('-');
+            for byte in eui64.iter_mut().rev() {
+                *byte = u8::from_str_radix(parts.next()?, 16).ok()?;
+            }
+            if parts.next().is_some() {
+                return None;
+            }
+            Node::Messaging(Messaging::Nvme {
+                namespace_id: a.number(0)?,
+                eui64,
+            })
+        }
+        // the uri might contain commas
+        "Uri" => Node::Messaging(Messaging::Uri {
+            uri: raw_args.as_bytes(),
+        }),
+        "UFS" => Node::Messaging(Messaging::Ufs {
+            target: a.number(0)?,
+            lun: a.number(1)?,
+        }),
+        "SD" => Node::Messaging(Messaging::Sd { slot: a.number(0)? }),
+        "eMMC" => Node::Messaging(Messaging::Emmc { slot: a.number(0)? }),
+        "HD" => {
+            // `HD(n,format,signature,start,size)` where the format is a name or a number,
+            // a numeric format may be followed by the signature type
+            let (format, signature, start) = match a.str(1) {
+                "MBR" => (
+                    PartitionFormat::Mbr,
+                    PartitionSignature::Mbr(a.number(2)?),
+                    3,
+                ),
+                "GPT" => (
+                    PartitionFormat::Gpt,
+                    PartitionSignature::Guid(a.guid(2)?),
+                    3,
+                ),
+                _ => {
+                    let format = match a.number(1)? {
+                        0x01 => PartitionFormat::Mbr,
+                        0x02 => PartitionFormat::Gpt,
+                        format => PartitionFormat::Unknown(format),
+                    };
+                    let (signature_type, signature, start) = if a.list.len() == 5 {
+                        (0x00, 2, 3)
+                    } else {
+                        (a.number(2)?, 3, 4)
+                    };
+                    let signature = match signature_type {
+                        0x00 => match a.number::<u64>(signature)? {
+                            0 => PartitionSignature::None,
+                            _ => return None,
+                        },
+                        0x01 => PartitionSignature::Mbr(a.number(signature)?),
+                        0x02 => PartitionSignature::Guid(a.guid(signature)?),
+                        signature_type => PartitionSignature::Unknown(
+                            signature_type,
+                            TryFrom::try_from(a.hex(signature)?.as_slice()).ok()?,
+                        ),
+                    };
+                    (format, signature, start)
+                }
+            };
+            Node::Media(Media::HardDrive {
+                partition_number: a.number(0)?,
+                partition_start: a.number(start)?,
+                partition_size: a.number(start + 1)?,
+                signature,
+                format,
+            })
+        }
+        "CDROM" => Node::Media(Media::CdRom {
+            boot_entry: a.number(0)?,
+            partition_start: a.number(1)?,
+            partition_size: a.number(2)?,
+        }),
+        "VenMedia" => {
+            bytes = a.hex(1)?;
+            Node::Media(Media::Vendor {
+                guid: a.guid(0)?,
+                data: &bytes,
+            })
+        }
+        "Media" => Node::Media(Media::Protocol { guid: a.guid(0)? }),
+        "FvFile" => Node::Media(Media::FirmwareFile { name: a.guid(0)? }),
+        "Fv" => Node::Media(Media::FirmwareVolume { name: a.guid(0)? }),
+        "Offset" => Node::Media(Media::RelativeOffsetRange {
+            start: a.number(0)?,
+            end: a.number(1)?,
+        }),
+        "RamDisk" => Node::Media(Media::RamDisk {
+            start: a.number(0)?,
+            end: a.number(1)?,
+            instance: a.number(2)?,
+            disk_type: a.guid(3)?,
+        }),
+        "BBS" => Node::Bbs(Bbs::Bbs {
+            device_type: a.number(0)?,
+            description: a.str(1).as_bytes(),
+            status_flag: a.number(2)?,
+        }),
+        "Path" => {
+            bytes = a.hex(2)?;
+            return builder
+                .push_raw(a.number(0)?, a.number(1)?, &bytes)
+                .ok()
+                .map(|_| ());
+        }
+        name => match ACPI_NAMES.iter().find(|&&(known, _)| known == name) {
+            Some(&(_, id)) => Node::Acpi(Acpi::Acpi {
+                hid: eisa_pnp_id(id),
+                uid: a.number(0)?,
+            }),
+            None => return None,
+        },
+    };
+    builder.push(&node).ok().map(|_| ())
+}
+
+/// Parses the text form, e.g. `PciRoot(0x0)/Pci(0x1,0x1)/Sata(0x0,0xFFFF,0x0)`,
+/// the text without parentheses is the file path node
+impl FromStr for DevicePathBuf {
+    type Err = DevicePathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut raw = Vec::new();
+        {
+            let mut builder = DevicePathBuilder::with_vec(&mut raw);
+            let mut depth = 0;
+            let mut quoted = false;
+            let mut start = 0;
+            let separators = s.char_indices().chain(Some((s.len(), '/')));
+            for (index, c) in separators {
+                match c {
+                    '"' => quoted = !quoted,
+                    '(' if !quoted => depth += 1,
+                    ')' if !quoted && depth > 0 => depth -= 1,
+                    '/' | ',' if !quoted && depth == 0 => {
+                        let text = &s[start..index];
+                        if !text.trim().is_empty() {
+                            push_node(&mut builder, text)
+                                .ok_or(DevicePathError::InvalidText { position: start })?;
+                        }
+                        if c == ',' {
+                            builder.end_instance()?;
+                        }
+                        start = index + 1;
+                    }
+                    _ => (),
+                }
+            }
+            // the rest of the text is not terminated by the separator
+            if depth != 0 || quoted {
+                return Err(DevicePathError::InvalidText { position: start });
+            }
+            builder.finish()?;
+        }
+        Ok(DevicePathBuf { inner: raw })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::node::Messaging;
+    use super::super::node::Node;
+    use super::super::DevicePathBuf;
+    use super::super::DevicePathBuilder;
+    use super::super::DevicePathError;
+
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    // the text is printed back as is and parsed back into the same bytes
+    fn round_trip(text: &str) -> DevicePathBuf {
+        let path: DevicePathBuf = text.parse().unwrap();
+        assert_eq!(path.to_string(), text);
+        let again: DevicePathBuf = path.to_string().parse().unwrap();
+        assert_eq!(again.as_bytes(), path.as_bytes());
+        path
+    }
+
+    fn single_node(text: &str) -> Vec<u8> {
+        let path: DevicePathBuf = text.parse().unwrap();
+        let nodes = path.nodes().collect::<Vec<_>>();
+        assert_eq!(nodes.len(), 1);
+        nodes[0].as_bytes().to_vec()
+    }
+
+    #[test]
+    fn boot_entry() {
+        let path = round_trip(
+            "PciRoot(0x0)/Pci(0x1,0x1)/Sata(0x0,0xFFFF,0x0)/\
+             HD(1,GPT,A5F39E3C-1234-4D2A-9C1E-0123456789AB,0x800,0x100000)/\
+             \\EFI\\BOOT\\BOOTX64.EFI",
+        );
+        let nodes = path.nodes().collect::<Vec<_>>();
+        assert_eq!(nodes.len(), 5);
+        assert_eq!(
+            nodes[0].as_bytes(),
+            &[0x02, 0x01, 0x0c, 0x00, 0xd0, 0x41, 0x03, 0x0a, 0x00, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(nodes[1].as_bytes(), &[0x01, 0x01, 0x06, 0x00, 0x01, 0x01]);
+        assert_eq!(
+            nodes[2].as_bytes(),
+            &[0x03, 0x12, 0x0a, 0x00, 0x00, 0x00, 0xff, 0xff, 0x00, 0x00]
+        );
+        assert_eq!(&nodes[3].as_bytes()[..4], &[0x04, 0x01, 0x2a, 0x00]);
+        assert_eq!(
+            &nodes[4].as_bytes()[..6],
+            &[0x04, 0x04, 0x30, 0x00, b'\\', 0x00]
+        );
+        assert_eq!(
+            &path.as_bytes()[(path.as_bytes().len() - 4)..],
+            &[0x7f, 0xff, 0x04, 0x00]
+        );
+    }
+
+    #[test]
+    fn multi_instance() {
+        let path = round_trip("PciRoot(0x0)/Pci(0x2,0x0),PciRoot(0x1)/Pci(0x3,0x0)");
+        let ends = path.nodes().filter(|node| node.is_end_instance()).count();
+        assert_eq!(ends, 1);
+        assert_eq!(path.nodes().count(), 5);
+    }
+
+    #[test]
+    fn ipv6_compressed() {
+        let path: DevicePathBuf = "IPv6(FE80::1,TCP,Static,::,0x40,2001:DB8::)"
+            .parse()
+            .unwrap();
+        let node = path.nodes().next().unwrap();
+        match node.node() {
+            Node::Messaging(Messaging::Ipv6 {
+                remote_address,
+                local_address,
+                gateway_address,
+                prefix_length,
+                ..
+            }) => {
+                let mut remote = [0; 16];
+                remote[0] = 0xfe;
+                remote[1] = 0x80;
+                remote[15] = 0x01;
+                assert_eq!(remote_address, remote);
+                assert_eq!(local_address, [0; 16]);
+                let mut gateway = [0; 16];
+                gateway[..4].copy_from_slice(&[0x20, 0x01, 0x0d, 0xb8]);
+                assert_eq!(gateway_address, gateway);
+                assert_eq!(prefix_length, 0x40);
+            }
+            node => panic!("unexpected node {:?}", node),
+        }
+        // printed in full
+        round_trip(&path.to_string());
+
+        assert!("IPv6(1::2::3,TCP,Static,::,0x40,::)"
+            .parse::<DevicePathBuf>()
+            .is_err());
+        assert!("IPv6(1:2:3:4:5:6:7:8:9,TCP,Static,::,0x40,::)"
+            .parse::<DevicePathBuf>()
+            .is_err());
+    }
+
+    #[test]
+    fn unknown_node() {
+        let mut raw = Vec::new();
+        {
+            let mut builder = DevicePathBuilder::with_vec(&mut raw);
+            builder.push_raw(0x01, 0x7f, &[0xab, 0xcd]).unwrap();
+            builder.push_raw(0x42, 0x01, &[]).unwrap();
+            builder.finish().unwrap();
+        }
+        let path = DevicePathBuf::from_vec(raw).unwrap();
+        assert_eq!(path.to_string(), "Path(1,127,ABCD)/Path(66,1,)");
+        let again: DevicePathBuf = path.to_string().parse().unwrap();
+        assert_eq!(again, path);
+    }
+
+    #[test]
+    fn nvme_eui64() {
+        let bytes = single_node("NVMe(0x1,00-11-22-33-44-55-66-77)");
+        assert_eq!(
+            bytes,
+            [
+                0x03, 0x17, 0x10, 0x00, 0x01, 0x00, 0x00, 0x00, 0x77, 0x66, 0x55, 0x44, 0x33, 0x22,
+                0x11, 0x00,
+            ]
+        );
+        round_trip("NVMe(0x1,00-11-22-33-44-55-66-77)");
+        assert!("NVMe(0x1,00-11-22)".parse::<DevicePathBuf>().is_err());
+        assert!("NVMe(0x1,00-11-22-33-44-55-66-77-88)"
+            .parse::<DevicePathBuf>()
+            .is_err());
+    }
+
+    #[test]
+    fn ata_numbers() {
+        let path: DevicePathBuf = "Ata(4)".parse().unwrap();
+        assert_eq!(path.to_string(), "Ata(0x4,Master,0x0)");
+        round_trip("Ata(0x4,Master,0x0)");
+        round_trip("Ata(Secondary,0x2,0x1)");
+        round_trip("Ata(Primary,Slave,0x0)");
+    }
+
+    #[test]
+    fn hard_drive_numbers() {
+        // the spec form with the implied signature type keeps the format number
+        let path: DevicePathBuf = "HD(1,0x1,0,0x800,0x1000)".parse().unwrap();
+        assert_eq!(path.to_string(), "HD(1,0x1,0x0,0,0x800,0x1000)");
+        let bytes = single_node("HD(1,0x1,0,0x800,0x1000)");
+        assert_eq!(&bytes[40..], &[0x01, 0x00]);
+        round_trip("HD(1,0x1,0x0,0,0x800,0x1000)");
+
+        let bytes = single_node("HD(2,0x2,0x1,0x12345678,0x800,0x1000)");
+        assert_eq!(&bytes[24..28], &[0x78, 0x56, 0x34, 0x12]);
+        assert_eq!(&bytes[40..], &[0x02, 0x01]);
+        round_trip("HD(2,0x2,0x1,0x12345678,0x800,0x1000)");
+
+        round_trip("HD(3,0x3,0x0,0,0x800,0x1000)");
+        round_trip("HD(4,0x1,0x2,A5F39E3C-1234-4D2A-9C1E-0123456789AB,0x800,0x1000)");
+        round_trip("HD(5,0x7,0x9,000102030405060708090A0B0C0D0E0F,0x800,0x1000)");
+        round_trip("HD(6,MBR,0x12345678,0x800,0x1000)");
+
+        assert!("HD(1,0x1,0x5,0102,0x800,0x1000)"
+            .parse::<DevicePathBuf>()
+            .is_err());
+        assert!("HD(1,0x3,1,0x800,0x1000)".parse::<DevicePathBuf>().is_err());
+    }
+
+    #[test]
+    fn malformed() {
+        let error = |text: &str| text.parse::<DevicePathBuf>().err();
+        assert_eq!(
+            error("Foo(1)"),
+            Some(DevicePathError::InvalidText { position: 0 })
+        );
+        assert_eq!(
+            error("PciRoot(0x0)/Bar(2)"),
+            Some(DevicePathError::InvalidText { position: 13 })
+        );
+        assert_eq!(
+            error("PciRoot(0x0)/Pci(zz,0x0)"),
+            Some(DevicePathError::InvalidText { position: 13 })
+        );
+        assert_eq!(
+            error("PciRoot(0x0)/Pci(0x1"),
+            Some(DevicePathError::InvalidText { position: 13 })
+        );
+        assert!(error("UsbWwid(0x1,0x2,0x3,\"serial)").is_some());
+        assert!(error("Pci(0x1,0x100)").is_some());
+        assert!(error("Path(1,2,ABC)").is_some());
+    }
+}
@@ -0,0 +1,56 @@
+use common::Guid;
+use common::Char16;
+use common::Status;
+use common::HasGuid;
+
+use boot_services::BootServices;
+
+use pool::PoolBox;
+
+use super::device_path::DevicePath;
+
+use alloc::vec::Vec;
+
+#[repr(C)]
+pub struct DevicePathFromText {
+    convert_text_to_device_node:
+        extern "efiapi" fn(/* in */ text_device_node: *const Char16) -> *mut u8,
+    convert_text_to_device_path:
+        extern "efiapi" fn(/* in */ text_device_path: *const Char16) -> *mut u8,
+}
+
+fn ucs2_with_nul(text: &str) -> Vec<Char16> {
+    text.encode_utf16().chain(Some(0)).collect()
+}
+
+impl DevicePathFromText {
+    /// The single node terminated by the end node, fails if the text is not recognized
+    pub fn text_to_node<'a>(
+        &self,
+        boot_services: &'a BootServices,
+        text: &str,
+    ) -> Result<PoolBox<'a, DevicePath>, Status> {
+        let convert_text_to_device_node = self.convert_text_to_device_node;
+        let raw = convert_text_to_device_node(ucs2_with_nul(text).as_ptr());
+        unsafe { DevicePath::from_pool(boot_services, raw) }.ok_or(Status::INVALID_PARAMETER)
+    }
+
+    pub fn text_to_path<'a>(
+        &self,
+        boot_services: &'a BootServices,
+        text: &str,
+    ) -> Result<PoolBox<'a, DevicePath>, Status> {
+        let convert_text_to_device_path = self.convert_text_to_device_path;
+        let raw = convert_text_to_device_path(ucs2_with_nul(text).as_ptr());
+        unsafe { DevicePath::from_pool(boot_services, raw) }.ok_or(Status::INVALID_PARAMETER)
+    }
+}
+
+impl HasGuid for DevicePathFromText {
+    const GUID: Guid = Guid(
+        0x05c99a21,
+        0xc70f,
+        0x4ad2,
+        [0x8a, 0x5f, 0x35, 0xdf, 0x33, 0x43, 0xf5, 0x1e],
+    );
+}
@@ -0,0 +1,87 @@
+use common::Guid;
+use common::Bool;
+use common::Char16;
+use common::Status;
+use common::HasGuid;
+
+use boot_services::BootServices;
+
+use pool::PoolSlice;
+
+use super::device_path::DevicePath;
+use super::device_path::DevicePathNode;
+
+#[repr(C)]
+pub struct DevicePathToText {
+    convert_device_node_to_text: extern "efiapi" fn(
+        /* in */ device_node: *const u8,
+        /* in */ display_only: Bool,
+        /* in */ allow_shortcuts: Bool,
+    ) -> *mut Char16,
+    convert_device_path_to_text: extern "efiapi" fn(
+        /* in */ device_path: *const u8,
+        /* in */ display_only: Bool,
+        /* in */ allow_shortcuts: Bool,
+    ) -> *mut Char16,
+}
+
+// the length of the firmware string without the null
+unsafe fn text<'a>(
+    boot_services: &'a BootServices,
+    raw: *mut Char16,
+) -> Result<PoolSlice<'a, Char16>, Status> {
+    if raw.is_null() {
+        return Err(Status::OUT_OF_RESOURCES);
+    }
+    let mut length = 0;
+    while *raw.add(length) != 0 {
+        length += 1;
+    }
+    Ok(PoolSlice::from_raw(boot_services, raw, length))
+}
+
+impl DevicePathToText {
+    /// The `display_only` gives the shorter form, which cannot be converted back,
+    /// the `allow_shortcuts` enables the short names like `PciRoot`
+    pub fn node_to_text<'a>(
+        &self,
+        boot_services: &'a BootServices,
+        node: &DevicePathNode,
+        display_only: bool,
+        allow_shortcuts: bool,
+    ) -> Result<PoolSlice<'a, Char16>, Status> {
+        let convert_device_node_to_text = self.convert_device_node_to_text;
+        let raw = convert_device_node_to_text(
+            node.as_bytes().as_ptr(),
+            display_only as _,
+            allow_shortcuts as _,
+        );
+        unsafe { text(boot_services, raw) }
+    }
+
+    /// The text is not null terminated
+    pub fn path_to_text<'a>(
+        &self,
+        boot_services: &'a BootServices,
+        device_path: &DevicePath,
+        display_only: bool,
+        allow_shortcuts: bool,
+    ) -> Result<PoolSlice<'a, Char16>, Status> {
+        let convert_device_path_to_text = self.convert_device_path_to_text;
+        let raw = convert_device_path_to_text(
+            device_path.as_ptr(),
+            display_only as _,
+            allow_shortcuts as _,
+        );
+        unsafe { text(boot_services, raw) }
+    }
+}
+
+impl HasGuid for DevicePathToText {
+    const GUID: Guid = Guid(
+        0x8b843e20,
+        0x8132,
+        0x4852,
+        [0x90, 0xcc, 0x55, 0x1a, 0x4e, 0x4a, 0x7f, 0x1c],
+    );
+}
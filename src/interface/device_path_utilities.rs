@@ -0,0 +1,155 @@
+use common::Guid;
+use common::Word;
+use common::Bool;
+use common::Status;
+use common::HasGuid;
+
+use boot_services::BootServices;
+
+use pool::PoolBox;
+
+use super::device_path::DevicePath;
+use super::device_path::DevicePathNode;
+
+use core::ptr;
+
+#[repr(C)]
+pub struct DevicePathUtilities {
+    get_device_path_size: extern "efiapi" fn(/* in */ device_path: *const u8) -> Word,
+    duplicate_device_path: extern "efiapi" fn(/* in */ device_path: *const u8) -> *mut u8,
+    append_device_path:
+        extern "efiapi" fn(/* in */ src1: *const u8, /* in */ src2: *const u8) -> *mut u8,
+    append_device_node: extern "efiapi" fn(
+        /* in */ device_path: *const u8,
+        /* in */ device_node: *const u8,
+    ) -> *mut u8,
+    append_device_path_instance: extern "efiapi" fn(
+        /* in */ device_path: *const u8,
+        /* in */ device_path_instance: *const u8,
+    ) -> *mut u8,
+    get_next_device_path_instance: extern "efiapi" fn(
+        /* in out */ device_path_instance: *mut *const u8,
+        /* out */ device_path_instance_size: *mut Word,
+    ) -> *mut u8,
+    is_device_path_multi_instance: extern "efiapi" fn(/* in */ device_path: *const u8) -> Bool,
+    create_device_node: extern "efiapi" fn(
+        /* in */ node_type: u8,
+        /* in */ node_sub_type: u8,
+        /* in */ node_length: u16,
+    ) -> *mut u8,
+}
+
+// the firmware returns null if it runs out of memory
+unsafe fn owned<'a>(
+    boot_services: &'a BootServices,
+    raw: *mut u8,
+) -> Result<PoolBox<'a, DevicePath>, Status> {
+    DevicePath::from_pool(boot_services, raw).ok_or(Status::OUT_OF_RESOURCES)
+}
+
+impl DevicePathUtilities {
+    /// The size in bytes including the end node
+    pub fn size(&self, device_path: &DevicePath) -> Word {
+        let get_device_path_size = self.get_device_path_size;
+        get_device_path_size(device_path.as_ptr())
+    }
+
+    pub fn duplicate<'a>(
+        &self,
+        boot_services: &'a BootServices,
+        device_path: &DevicePath,
+    ) -> Result<PoolBox<'a, DevicePath>, Status> {
+        let duplicate_device_path = self.duplicate_device_path;
+        unsafe { owned(boot_services, duplicate_device_path(device_path.as_ptr())) }
+    }
+
+    /// Appends the `second` path to the last instance of the `first` path
+    pub fn append<'a>(
+        &self,
+        boot_services: &'a BootServices,
+        first: &DevicePath,
+        second: &DevicePath,
+    ) -> Result<PoolBox<'a, DevicePath>, Status> {
+        let append_device_path = self.append_device_path;
+        let raw = append_device_path(first.as_ptr(), second.as_ptr());
+        unsafe { owned(boot_services, raw) }
+    }
+
+    pub fn append_node<'a>(
+        &self,
+        boot_services: &'a BootServices,
+        device_path: &DevicePath,
+        node: &DevicePathNode,
+    ) -> Result<PoolBox<'a, DevicePath>, Status> {
+        let append_device_node = self.append_device_node;
+        let raw = append_device_node(device_path.as_ptr(), node.as_bytes().as_ptr());
+        unsafe { owned(boot_services, raw) }
+    }
+
+    /// Appends the `instance` as the new instance of the multi-instance path
+    pub fn append_instance<'a>(
+        &self,
+        boot_services: &'a BootServices,
+        device_path: &DevicePath,
+        instance: &DevicePath,
+    ) -> Result<PoolBox<'a, DevicePath>, Status> {
+        let append_device_path_instance = self.append_device_path_instance;
+        let raw = append_device_path_instance(device_path.as_ptr(), instance.as_ptr());
+        unsafe { owned(boot_services, raw) }
+    }
+
+    /// Copies the first instance of the `device_path` and returns the rest of it,
+    /// the rest is `None` after the last instance
+    pub fn next_instance<'a, 'p>(
+        &self,
+        boot_services: &'a BootServices,
+        device_path: &'p DevicePath,
+    ) -> Result<(PoolBox<'a, DevicePath>, Option<&'p DevicePath>), Status> {
+        let get_next_device_path_instance = self.get_next_device_path_instance;
+        let mut rest = device_path.as_ptr();
+        let mut size = 0;
+        let raw = get_next_device_path_instance(&mut rest, &mut size);
+        let instance = unsafe { owned(boot_services, raw)? };
+        let rest = if rest.is_null() {
+            None
+        } else {
+            Some(unsafe { DevicePath::from_ptr(rest) })
+        };
+        Ok((instance, rest))
+    }
+
+    pub fn is_multi_instance(&self, device_path: &DevicePath) -> bool {
+        let is_device_path_multi_instance = self.is_device_path_multi_instance;
+        is_device_path_multi_instance(device_path.as_ptr()) != 0
+    }
+
+    /// The node with the zeroed data, it is not terminated by the end node
+    pub fn create_node<'a>(
+        &self,
+        boot_services: &'a BootServices,
+        node_type: u8,
+        node_sub_type: u8,
+        node_length: u16,
+    ) -> Result<PoolBox<'a, DevicePathNode>, Status> {
+        // shorter than the header
+        if node_length < 4 {
+            return Err(Status::INVALID_PARAMETER);
+        }
+        let create_device_node = self.create_device_node;
+        let raw = create_device_node(node_type, node_sub_type, node_length);
+        if raw.is_null() {
+            return Err(Status::OUT_OF_RESOURCES);
+        }
+        let node = ptr::slice_from_raw_parts_mut(raw, node_length as usize);
+        Ok(unsafe { PoolBox::from_raw(boot_services, node as *mut DevicePathNode) })
+    }
+}
+
+impl HasGuid for DevicePathUtilities {
+    const GUID: Guid = Guid(
+        0x0379be4e,
+        0xd706,
+        0x437d,
+        [0xb0, 0x37, 0xed, 0xb8, 0x2f, 0xb7, 0x72, 0xa4],
+    );
+}
@@ -1,51 +1,120 @@
 use common::Char16;
+use common::Event;
+use common::Bool;
 use common::Status;
 use common::Guid;
 use common::Word;
 use common::HasGuid;
+use common::Error;
+use common::Revision;
+use common::decode_ucs2;
 
 use core::ptr;
 use core::mem;
+use core::cmp;
+
+use core::ops::Deref;
+
+use core::char::REPLACEMENT_CHARACTER;
+
+use core::future::Future;
+use core::pin::Pin;
+use core::task::Context;
+use core::task::Poll;
+
+use alloc::vec::Vec;
+use alloc::string::String;
+use alloc::boxed::Box;
+
+use array::Pointer;
+
+use boot_services::BootServices;
+use boot_services::EventType;
+use boot_services::TPL_CALLBACK;
+
+use path::Path;
+use path::ToPath;
 
 #[repr(C)]
 pub struct File {
     revision: u64,
 
-    open: extern "win64" fn(
+    open: extern "efiapi" fn(
         /* in */ this: *const File,
         /* out */ new_handle: *mut *const File,
         /* in */ file_name: *const Char16,
         /* in */ open_mode: u64,
         /* in */ attributes: u64,
     ) -> Status,
-    close: extern "win64" fn(/* in */ this: *const File) -> Status,
-    delete: extern "win64" fn(/* in */ this: *const File) -> Status,
-    read: extern "win64" fn(
+    close: extern "efiapi" fn(/* in */ this: *const File) -> Status,
+    delete: extern "efiapi" fn(/* in */ this: *const File) -> Status,
+    read: extern "efiapi" fn(
         /* in */ this: *const File,
         /* in out */ buffer_size: *mut Word,
         /* in */ buffer: *mut (),
     ) -> Status,
-    write: extern "win64" fn(/* in */ this: *const File) -> Status,
-    get_position: extern "win64" fn(/* in */ this: *const File) -> Status,
-    set_position: extern "win64" fn(/* in */ this: *const File) -> Status,
-    get_info: extern "win64" fn(
+    write: extern "efiapi" fn(
+        /* in */ this: *const File,
+        /* in out */ buffer_size: *mut Word,
+        /* in */ buffer: *const (),
+    ) -> Status,
+    get_position: extern "efiapi" fn(
+        /* in */ this: *const File,
+        /* out */ position: *mut u64,
+    ) -> Status,
+    set_position:
+        extern "efiapi" fn(/* in */ this: *const File, /* in */ position: u64) -> Status,
+    get_info: extern "efiapi" fn(
         /* in */ this: *const File,
         /* in */ information_type: *const Guid,
         /* in out */ buffer_size: *mut Word,
         /* out */ buffer: *mut (),
     ) -> Status,
-    set_info: extern "win64" fn(/* in */ this: *const File) -> Status,
-    flush: extern "win64" fn(/* in */ this: *const File) -> Status,
+    set_info: extern "efiapi" fn(
+        /* in */ this: *const File,
+        /* in */ information_type: *const Guid,
+        /* in */ buffer_size: Word,
+        /* in */ buffer: *const (),
+    ) -> Status,
+    flush: extern "efiapi" fn(/* in */ this: *const File) -> Status,
 
     // Available only if revision == REVISION2
-    open_ex: extern "win64" fn(/* in */ this: *const File) -> Status,
-    read_ex: extern "win64" fn(/* in */ this: *const File) -> Status,
-    write_ex: extern "win64" fn(/* in */ this: *const File) -> Status,
-    flush_ex: extern "win64" fn(/* in */ this: *const File) -> Status,
+    open_ex: extern "efiapi" fn(
+        /* in */ this: *const File,
+        /* out */ new_handle: *mut *const File,
+        /* in */ file_name: *const Char16,
+        /* in */ open_mode: u64,
+        /* in */ attributes: u64,
+        /* in out */ token: *mut FileIoTokenRaw,
+    ) -> Status,
+    read_ex: extern "efiapi" fn(
+        /* in */ this: *const File,
+        /* in out */ token: *mut FileIoTokenRaw,
+    ) -> Status,
+    write_ex: extern "efiapi" fn(
+        /* in */ this: *const File,
+        /* in out */ token: *mut FileIoTokenRaw,
+    ) -> Status,
+    flush_ex: extern "efiapi" fn(
+        /* in */ this: *const File,
+        /* in out */ token: *mut FileIoTokenRaw,
+    ) -> Status,
+}
+
+const REVISION1: u64 = 0x00010000;
+const REVISION2: u64 = 0x00020000;
+
+impl Revision for File {
+    fn revision(&self) -> u64 {
+        self.revision
+    }
 }
 
-const _REVISION1: u32 = 0x00010000;
-const _REVISION2: u32 = 0x00020000;
+// some firmware fails to transfer large buffers at once
+const CHUNK_SIZE: usize = 0x100000;
+
+// Setting the position to this value moves it to the end of the file
+const END_OF_FILE: u64 = 0xffffffffffffffff;
 
 bitflags! {
     pub struct OpenMode: u64 {
@@ -67,10 +136,53 @@ bitflags! {
     }
 }
 
-const MAX_FILE_NAME_SIZE: usize = 64;
+/// Information about a file that can be queried with `File::get_info`
+/// and changed with `File::set_info`, `HasGuid` gives the information type
+pub trait FileInfoType: HasGuid + Sized {
+    /// Parses the variable-length record filled by the firmware,
+    /// `None` if the record or the size it claims is shorter than its fixed part
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+    /// Builds the record passed to the firmware
+    fn to_bytes(&self) -> Vec<u8>;
+}
+
+// reads a null terminated UCS-2 string, stops at the end of the slice if there is no null
+fn read_name(bytes: &[u8]) -> Vec<Char16> {
+    bytes
+        .chunks(2)
+        .filter(|pair| pair.len() == 2)
+        .map(|pair| Char16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|&c| c != 0)
+        .collect()
+}
+
+// appends the string and the terminating null
+fn write_name(bytes: &mut Vec<u8>, name: &[Char16]) {
+    for c in name.iter().chain(&[0]) {
+        bytes.extend_from_slice(&c.to_le_bytes());
+    }
+}
+
+// the fixed part of the record takes `size` bytes, it might be less than the size of `H`
+// because the name follows the last field without the trailing padding
+unsafe fn read_header<H>(bytes: &[u8], size: usize) -> Option<H> {
+    assert!(mem::size_of::<H>() >= size);
+    if bytes.len() < size {
+        return None;
+    }
+    let mut header: H = mem::zeroed();
+    ptr::copy_nonoverlapping(bytes.as_ptr(), &mut header as *mut H as *mut u8, size);
+    Some(header)
+}
+
+// the name lies between the fixed part and the size the record claims
+fn name_range(bytes: &[u8], header_size: usize, record_size: u64) -> Option<&[u8]> {
+    let end = cmp::min(record_size, bytes.len() as u64) as usize;
+    bytes.get(header_size..end)
+}
 
 #[repr(C)]
-struct FileInfoRaw {
+struct FileInfoHeader {
     size: u64,
     file_size: u64,
     physical_size: u64,
@@ -78,27 +190,36 @@ struct FileInfoRaw {
     last_access_time: Time,
     modification_time: Time,
     attributes: Attributes,
-    file_name: [Char16; MAX_FILE_NAME_SIZE],
 }
 
-impl FileInfoRaw {
+impl FileInfoHeader {
     const SIZE: usize = mem::size_of::<Self>();
+}
 
-    fn empty() -> Self {
-        FileInfoRaw {
-            size: Self::SIZE as _,
-            file_size: 0,
-            physical_size: 0,
-            create_time: Time::default(),
-            last_access_time: Time::default(),
-            modification_time: Time::default(),
-            attributes: Attributes::NULL,
-            file_name: [0; MAX_FILE_NAME_SIZE],
-        }
+pub struct FileInfo {
+    pub file_size: u64,
+    pub physical_size: u64,
+    pub create_time: Time,
+    pub last_access_time: Time,
+    pub modification_time: Time,
+    pub attributes: Attributes,
+    /// The name without the terminating null character
+    pub name: Vec<Char16>,
+}
+
+impl FileInfo {
+    pub fn is_directory(&self) -> bool {
+        self.attributes.contains(Attributes::DIRECTORY)
+    }
+
+    // `.` and `..` entries present in every directory except the root
+    fn is_dot(&self) -> bool {
+        const DOT: Char16 = b'.' as _;
+        self.name == [DOT] || self.name == [DOT, DOT]
     }
 }
 
-impl HasGuid for FileInfoRaw {
+impl HasGuid for FileInfo {
     const GUID: Guid = Guid(
         0x09576e92,
         0x6d3f,
@@ -107,34 +228,131 @@ impl HasGuid for FileInfoRaw {
     );
 }
 
-pub struct FileInfo {
-    pub size: u64,
-    pub physical_size: u64,
-    pub create_time: Time,
-    pub last_access_time: Time,
-    pub modification_time: Time,
-    pub name: [Char16; MAX_FILE_NAME_SIZE],
+impl FileInfoType for FileInfo {
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let header: FileInfoHeader = unsafe { read_header(bytes, FileInfoHeader::SIZE)? };
+        let name = name_range(bytes, FileInfoHeader::SIZE, header.size)?;
+        Some(FileInfo {
+            file_size: header.file_size,
+            physical_size: header.physical_size,
+            create_time: header.create_time,
+            last_access_time: header.last_access_time,
+            modification_time: header.modification_time,
+            attributes: header.attributes,
+            name: read_name(name),
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let size = FileInfoHeader::SIZE + (self.name.len() + 1) * 2;
+        let mut bytes = Vec::with_capacity(size);
+        bytes.extend_from_slice(&(size as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.file_size.to_le_bytes());
+        bytes.extend_from_slice(&self.physical_size.to_le_bytes());
+        self.create_time.write(&mut bytes);
+        self.last_access_time.write(&mut bytes);
+        self.modification_time.write(&mut bytes);
+        bytes.extend_from_slice(&self.attributes.bits().to_le_bytes());
+        write_name(&mut bytes, &self.name);
+        bytes
+    }
 }
 
-impl FileInfo {
-    pub const MAX_FILE_NAME_SIZE: usize = MAX_FILE_NAME_SIZE;
+#[repr(C)]
+struct FileSystemInfoHeader {
+    size: u64,
+    read_only: Bool,
+    volume_size: u64,
+    free_space: u64,
+    block_size: u32,
 }
 
-impl From<FileInfoRaw> for FileInfo {
-    fn from(raw: FileInfoRaw) -> Self {
-        FileInfo {
-            size: raw.file_size,
-            physical_size: raw.physical_size,
-            create_time: raw.create_time.clone(),
-            last_access_time: raw.last_access_time.clone(),
-            modification_time: raw.modification_time.clone(),
-            name: raw.file_name,
-        }
+impl FileSystemInfoHeader {
+    // the label starts right after `block_size`
+    const SIZE: usize = 36;
+}
+
+/// Information about the volume, query it from the root directory
+pub struct FileSystemInfo {
+    pub read_only: bool,
+    /// In bytes
+    pub volume_size: u64,
+    /// In bytes
+    pub free_space: u64,
+    pub block_size: u32,
+    /// The label without the terminating null character
+    pub volume_label: Vec<Char16>,
+}
+
+impl HasGuid for FileSystemInfo {
+    const GUID: Guid = Guid(
+        0x09576e93,
+        0x6d3f,
+        0x11d2,
+        [0x8e, 0x39, 0x00, 0xa0, 0xc9, 0x69, 0x72, 0x3b],
+    );
+}
+
+impl FileInfoType for FileSystemInfo {
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let header: FileSystemInfoHeader =
+            unsafe { read_header(bytes, FileSystemInfoHeader::SIZE)? };
+        let volume_label = name_range(bytes, FileSystemInfoHeader::SIZE, header.size)?;
+        Some(FileSystemInfo {
+            read_only: header.read_only != 0,
+            volume_size: header.volume_size,
+            free_space: header.free_space,
+            block_size: header.block_size,
+            volume_label: read_name(volume_label),
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let size = FileSystemInfoHeader::SIZE + (self.volume_label.len() + 1) * 2;
+        let mut bytes = Vec::with_capacity(size);
+        bytes.extend_from_slice(&(size as u64).to_le_bytes());
+        bytes.push(self.read_only as u8);
+        // the padding before `volume_size`
+        bytes.extend_from_slice(&[0; 7]);
+        bytes.extend_from_slice(&self.volume_size.to_le_bytes());
+        bytes.extend_from_slice(&self.free_space.to_le_bytes());
+        bytes.extend_from_slice(&self.block_size.to_le_bytes());
+        write_name(&mut bytes, &self.volume_label);
+        bytes
+    }
+}
+
+/// Only the label of the volume, query it from the root directory
+pub struct FileSystemVolumeLabel {
+    /// The label without the terminating null character
+    pub volume_label: Vec<Char16>,
+}
+
+impl HasGuid for FileSystemVolumeLabel {
+    const GUID: Guid = Guid(
+        0xdb47d7d3,
+        0xfe81,
+        0x11d3,
+        [0x9a, 0x35, 0x00, 0x90, 0x27, 0x3f, 0xc1, 0x4d],
+    );
+}
+
+impl FileInfoType for FileSystemVolumeLabel {
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(FileSystemVolumeLabel {
+            volume_label: read_name(bytes),
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity((self.volume_label.len() + 1) * 2);
+        write_name(&mut bytes, &self.volume_label);
+        bytes
     }
 }
 
 #[repr(C)]
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Copy)]
 pub struct Time {
     year: u16,  // 1900 – 9999
     month: u8,  // 1 – 12
@@ -149,41 +367,599 @@ pub struct Time {
     _pad2: u8,
 }
 
+impl Time {
+    // the same layout as the struct, padding is zeroed
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.year.to_le_bytes());
+        bytes.extend_from_slice(&[self.month, self.day, self.hour, self.minute, self.second, 0]);
+        bytes.extend_from_slice(&self.nanosecond.to_le_bytes());
+        bytes.extend_from_slice(&self.time_zone.to_le_bytes());
+        bytes.extend_from_slice(&[self.day_light, 0]);
+    }
+}
+
 impl File {
+    /// Opens the path relative to `self` in a single call
     pub fn open(
         &self,
-        file_name: &[Char16],
+        path: &Path,
         open_mode: OpenMode,
         attributes: Attributes,
-    ) -> Result<&Self, Status> {
-        assert!(self.revision as u32 >= _REVISION1);
+    ) -> Result<FileHandle, Error> {
+        self.require_revision(REVISION1)?;
         let open = self.open;
         let mut other: *const File = ptr::null_mut();
-        let c_str = file_name.as_ptr();
-        open(self, &mut other, c_str, open_mode.bits(), attributes.bits()).check(unsafe { &*other })
+        let name = path.to_ucs2_with_nul();
+        let c_str = name.as_ptr();
+        let status = open(self, &mut other, c_str, open_mode.bits(), attributes.bits());
+        Ok(status.check_map(|| unsafe { FileHandle::from_raw(other) })?)
     }
-    pub fn close(&self) -> Result<(), Status> {
-        assert!(self.revision as u32 >= _REVISION1);
+    /// Opens the directories on the way one by one, the `open_mode` and `attributes`
+    /// apply only to the last component, an invalid path gives `INVALID_PARAMETER`
+    pub fn open_path<P>(
+        &self,
+        path: &P,
+        open_mode: OpenMode,
+        attributes: Attributes,
+    ) -> Result<FileHandle, Error>
+    where
+        P: ToPath + ?Sized,
+    {
+        let path = path.to_path()?;
+        let components = path.components().collect::<Vec<_>>();
+        let (last, directories) = match components.split_last() {
+            Some(split) => split,
+            None => return self.open(&path, open_mode, attributes),
+        };
+        let mut current = if path.is_absolute() {
+            Some(self.open(Path::root(), OpenMode::READ, Attributes::NULL)?)
+        } else {
+            None
+        };
+        for directory in directories {
+            let parent: &File = current.as_ref().map_or(self, |file| file);
+            current = Some(parent.open(directory, OpenMode::READ, Attributes::NULL)?);
+        }
+        let parent: &File = current.as_ref().map_or(self, |file| file);
+        parent.open(last, open_mode, attributes)
+    }
+    // every revision has `close` and `delete`, and the owned handle must not leak,
+    // so these two are not gated by the revision
+    fn close(&self) -> Result<(), Error> {
         let close = self.close;
-        close(self).check(())
+        Ok(close(self).check(())?)
+    }
+    pub fn read(&self, buffer: &mut [u8]) -> Result<Word, Error> {
+        let (status, size) = self.read_raw(buffer)?;
+        Ok(status.check(size)?)
     }
-    pub fn read(&self, buffer: &mut [u8]) -> Result<Word, Status> {
-        assert!(self.revision as u32 >= _REVISION1);
+    // on `BUFFER_TOO_SMALL` the size is the required buffer size
+    fn read_raw(&self, buffer: &mut [u8]) -> Result<(Status, Word), Error> {
+        self.require_revision(REVISION1)?;
         let read = self.read;
         let mut size = buffer.len() as Word;
         let ptr = buffer.as_mut_ptr();
-        read(self, &mut size, ptr as _).check(size)
+        Ok((read(self, &mut size, ptr as _), size))
+    }
+    pub fn write(&self, buffer: &[u8]) -> Result<Word, Error> {
+        self.require_revision(REVISION1)?;
+        let write = self.write;
+        let mut size = buffer.len() as Word;
+        let ptr = buffer.as_ptr();
+        Ok(write(self, &mut size, ptr as _).check(size)?)
+    }
+    /// Writes the whole buffer in chunks
+    pub fn write_all(&self, buffer: &[u8]) -> Result<(), Error> {
+        let mut written = 0;
+        while written < buffer.len() {
+            let end = cmp::min(buffer.len(), written + CHUNK_SIZE);
+            match self.write(&buffer[written..end])? {
+                0 => return Err(Status::VOLUME_FULL.into()),
+                size => written += size,
+            }
+        }
+        Ok(())
+    }
+    /// Reads from the current position to the end of the file,
+    /// the size is taken from the file info and the reading is done in chunks
+    pub fn read_to_end(&self) -> Result<Vec<u8>, Error> {
+        let size = self.get_file_info()?.file_size;
+        let position = self.position()?;
+        let mut buffer = vec![0; size.saturating_sub(position) as usize];
+        let mut filled = 0;
+        while filled < buffer.len() {
+            let end = cmp::min(buffer.len(), filled + CHUNK_SIZE);
+            match self.read(&mut buffer[filled..end])? {
+                // the file is shorter than its info said
+                0 => break,
+                size => filled += size,
+            }
+        }
+        buffer.truncate(filled);
+        Ok(buffer)
+    }
+    /// Decodes UTF-16 if the content starts with its byte order mark, otherwise UTF-8,
+    /// invalid sequences are replaced with `U+FFFD`
+    pub fn read_to_string(&self) -> Result<String, Error> {
+        let bytes = self.read_to_end()?;
+        let utf16 = |bytes: &[u8], from: fn([u8; 2]) -> u16| {
+            let units = bytes.chunks(2).map(|pair| match *pair {
+                [a, b] => from([a, b]),
+                _ => REPLACEMENT_CHARACTER as u16,
+            });
+            decode_ucs2(units).collect()
+        };
+        Ok(match bytes.get(..2) {
+            Some(&[0xff, 0xfe]) => utf16(&bytes[2..], u16::from_le_bytes),
+            Some(&[0xfe, 0xff]) => utf16(&bytes[2..], u16::from_be_bytes),
+            _ => {
+                let bytes = if bytes.starts_with(&[0xef, 0xbb, 0xbf]) {
+                    &bytes[3..]
+                } else {
+                    &bytes[..]
+                };
+                String::from_utf8_lossy(bytes).into_owned()
+            }
+        })
+    }
+    pub fn position(&self) -> Result<u64, Error> {
+        self.require_revision(REVISION1)?;
+        let get_position = self.get_position;
+        let mut position = 0;
+        Ok(get_position(self, &mut position).check(position)?)
+    }
+    pub fn seek(&self, position: u64) -> Result<(), Error> {
+        self.require_revision(REVISION1)?;
+        let set_position = self.set_position;
+        Ok(set_position(self, position).check(())?)
     }
-    pub fn get_file_info(&self) -> Result<FileInfo, Status> {
-        assert!(self.revision as u32 >= _REVISION1);
+    pub fn seek_to_end(&self) -> Result<(), Error> {
+        self.seek(END_OF_FILE)
+    }
+    pub fn flush(&self) -> Result<(), Error> {
+        self.require_revision(REVISION1)?;
+        let flush = self.flush;
+        Ok(flush(self).check(())?)
+    }
+    // the handle is closed even if the file was not deleted
+    fn delete(&self) -> Result<(), Error> {
+        let delete = self.delete;
+        Ok(delete(self).check(())?)
+    }
+    /// Queries the information of type `I`, the buffer is sized as the firmware requests,
+    /// a malformed record gives `VOLUME_CORRUPTED`
+    pub fn get_info<I: FileInfoType>(&self) -> Result<I, Error> {
+        self.require_revision(REVISION1)?;
         let get_info = self.get_info;
-        let mut file_info = FileInfoRaw::empty();
-        let size = FileInfoRaw::SIZE;
-        get_info(
+        let mut buffer = Vec::new();
+        loop {
+            let mut size = buffer.len() as Word;
+            let status = get_info(self, &I::GUID, &mut size, buffer.as_mut_ptr() as _);
+            // the firmware must ask for more than it was given, otherwise it would loop forever
+            if status == Status::BUFFER_TOO_SMALL && size > buffer.len() {
+                buffer.resize(size, 0);
+                continue;
+            }
+            let buffer = &buffer;
+            return Ok(status.check_flat_map(|| {
+                I::from_bytes(&buffer[..size]).ok_or(Status::VOLUME_CORRUPTED)
+            })?);
+        }
+    }
+    pub fn set_info<I: FileInfoType>(&self, info: &I) -> Result<(), Error> {
+        self.require_revision(REVISION1)?;
+        let set_info = self.set_info;
+        let buffer = info.to_bytes();
+        Ok(set_info(self, &I::GUID, buffer.len() as _, buffer.as_ptr() as _).check(())?)
+    }
+    pub fn get_file_info(&self) -> Result<FileInfo, Error> {
+        self.get_info()
+    }
+    /// Asynchronous `open`, requires the revision 2 of the protocol
+    pub fn open_ex<'a>(
+        &'a self,
+        boot_services: &'a BootServices,
+        path: &Path,
+        open_mode: OpenMode,
+        attributes: Attributes,
+    ) -> Result<OpenToken<'a>, Error> {
+        self.require_revision(REVISION2)?;
+        let open_ex = self.open_ex;
+        let mut token = OpenToken {
+            token: FileIoToken::new(boot_services, Vec::new())?,
+            new_handle: Box::new(ptr::null()),
+            name: path.to_ucs2_with_nul(),
+        };
+        let status = open_ex(
             self,
-            &FileInfoRaw::GUID,
-            &mut (size as _),
-            &mut file_info as *mut FileInfoRaw as _,
-        ).check(file_info.into())
+            &mut *token.new_handle,
+            token.name.as_ptr(),
+            open_mode.bits(),
+            attributes.bits(),
+            &mut *token.token.raw,
+        );
+        token.token.start(status).map(|()| token)
+    }
+    /// Asynchronous `read`, requires the revision 2 of the protocol,
+    /// reads at most `buffer.len()` bytes
+    pub fn read_ex<'a>(
+        &'a self,
+        boot_services: &'a BootServices,
+        buffer: Vec<u8>,
+    ) -> Result<FileIoToken<'a>, Error> {
+        self.require_revision(REVISION2)?;
+        let read_ex = self.read_ex;
+        let mut token = FileIoToken::new(boot_services, buffer)?;
+        let status = read_ex(self, &mut *token.raw);
+        token.start(status).map(|()| token)
+    }
+    /// Asynchronous `write`, requires the revision 2 of the protocol
+    pub fn write_ex<'a>(
+        &'a self,
+        boot_services: &'a BootServices,
+        buffer: Vec<u8>,
+    ) -> Result<FileIoToken<'a>, Error> {
+        self.require_revision(REVISION2)?;
+        let write_ex = self.write_ex;
+        let mut token = FileIoToken::new(boot_services, buffer)?;
+        let status = write_ex(self, &mut *token.raw);
+        token.start(status).map(|()| token)
+    }
+    /// Asynchronous `flush`, requires the revision 2 of the protocol
+    pub fn flush_ex<'a>(
+        &'a self,
+        boot_services: &'a BootServices,
+    ) -> Result<FileIoToken<'a>, Error> {
+        self.require_revision(REVISION2)?;
+        let flush_ex = self.flush_ex;
+        let mut token = FileIoToken::new(boot_services, Vec::new())?;
+        let status = flush_ex(self, &mut *token.raw);
+        token.start(status).map(|()| token)
+    }
+    pub fn is_directory(&self) -> Result<bool, Error> {
+        self.get_file_info().map(|info| info.is_directory())
+    }
+}
+
+/// Owns the opened file, closes it on drop
+pub struct FileHandle {
+    raw: Pointer<File>,
+}
+
+impl Deref for FileHandle {
+    type Target = File;
+
+    fn deref(&self) -> &Self::Target {
+        self.raw.as_ref()
+    }
+}
+
+impl Drop for FileHandle {
+    fn drop(&mut self) {
+        let _ = self.raw.as_ref().close();
+    }
+}
+
+pub enum FileType {
+    Regular(RegularFile),
+    Directory(Directory),
+}
+
+impl FileHandle {
+    /// # Safety
+    ///
+    /// The `raw` must be an opened file not owned by anything else
+    pub unsafe fn from_raw(raw: *const File) -> Self {
+        FileHandle {
+            raw: Pointer::from_raw(raw as _),
+        }
+    }
+
+    /// The caller is responsible for closing the file
+    pub fn into_raw(self) -> *const File {
+        let raw = self.raw.as_ref() as *const File;
+        mem::forget(self);
+        raw
+    }
+
+    /// Same as drop, but reports the status
+    pub fn close(self) -> Result<(), Error> {
+        let status = self.raw.as_ref().close();
+        mem::forget(self);
+        status
+    }
+
+    /// Closes and deletes the file, the file is closed even if it was not deleted
+    pub fn delete(self) -> Result<(), Error> {
+        let status = self.raw.as_ref().delete();
+        mem::forget(self);
+        status
+    }
+
+    /// Checks the `DIRECTORY` attribute
+    pub fn into_type(self) -> Result<FileType, Error> {
+        if self.is_directory()? {
+            Ok(FileType::Directory(Directory::new(self)))
+        } else {
+            Ok(FileType::Regular(RegularFile { handle: self }))
+        }
+    }
+
+    /// Returns `None` and closes the file if it is a directory or its type is unknown
+    pub fn into_regular_file(self) -> Option<RegularFile> {
+        match self.into_type() {
+            Ok(FileType::Regular(file)) => Some(file),
+            _ => None,
+        }
+    }
+
+    /// Returns `None` and closes the file if it is not a directory or its type is unknown
+    pub fn into_directory(self) -> Option<Directory> {
+        match self.into_type() {
+            Ok(FileType::Directory(directory)) => Some(directory),
+            _ => None,
+        }
+    }
+}
+
+pub struct RegularFile {
+    handle: FileHandle,
+}
+
+impl Deref for RegularFile {
+    type Target = File;
+
+    fn deref(&self) -> &Self::Target {
+        &self.handle
+    }
+}
+
+impl RegularFile {
+    pub fn into_handle(self) -> FileHandle {
+        self.handle
+    }
+}
+
+// enough for the header and a 64 characters name, grows if some entry does not fit
+const DIRECTORY_BUFFER_SIZE: usize = FileInfoHeader::SIZE + 64 * 2;
+
+/// Reading a directory yields one `EFI_FILE_INFO` record per call
+pub struct Directory {
+    handle: FileHandle,
+    buffer: Vec<u8>,
+}
+
+impl Deref for Directory {
+    type Target = File;
+
+    fn deref(&self) -> &Self::Target {
+        &self.handle
+    }
+}
+
+impl Directory {
+    fn new(handle: FileHandle) -> Self {
+        Directory {
+            handle,
+            buffer: vec![0; DIRECTORY_BUFFER_SIZE],
+        }
+    }
+
+    pub fn into_handle(self) -> FileHandle {
+        self.handle
+    }
+
+    /// Restarts the reading from the first entry
+    pub fn reset(&self) -> Result<(), Error> {
+        self.handle.seek(0)
+    }
+
+    /// Returns `None` when there are no more entries, a malformed entry gives `VOLUME_CORRUPTED`
+    pub fn read_entry(&mut self) -> Result<Option<FileInfo>, Error> {
+        loop {
+            let (status, size) = self.handle.read_raw(&mut self.buffer)?;
+            if status == Status::BUFFER_TOO_SMALL && size > self.buffer.len() {
+                self.buffer.resize(size, 0);
+                continue;
+            }
+            let buffer = &self.buffer;
+            return Ok(status.check_flat_map(|| {
+                if size == 0 {
+                    Ok(None)
+                } else {
+                    FileInfo::from_bytes(&buffer[..size])
+                        .map(Some)
+                        .ok_or(Status::VOLUME_CORRUPTED)
+                }
+            })?);
+        }
+    }
+
+    pub fn entries(&mut self, skip_dots: bool) -> Entries<'_> {
+        Entries {
+            directory: self,
+            skip_dots,
+        }
+    }
+}
+
+pub struct Entries<'a> {
+    directory: &'a mut Directory,
+    skip_dots: bool,
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = Result<FileInfo, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.directory.read_entry() {
+                Ok(Some(ref info)) if self.skip_dots && info.is_dot() => continue,
+                Ok(Some(info)) => return Some(Ok(info)),
+                Ok(None) => return None,
+                Err(status) => return Some(Err(status)),
+            }
+        }
+    }
+}
+
+#[repr(C)]
+struct FileIoTokenRaw {
+    event: Event,
+    status: Status,
+    buffer_size: Word,
+    buffer: *mut (),
+}
+
+/// Request started by one of the revision 2 functions, the firmware signals the event
+/// when it is done. The token owns the buffer while the firmware uses it,
+/// dropping the token before the completion blocks until the request is done.
+pub struct FileIoToken<'a> {
+    boot_services: &'a BootServices,
+    // the firmware keeps the pointer, so it must not move
+    raw: Box<FileIoTokenRaw>,
+    buffer: Vec<u8>,
+    // `check_event` clears the signaled state, so once the completion is seen
+    // the event must not be waited for again
+    done: bool,
+    result: Option<Result<Vec<u8>, Error>>,
+}
+
+impl<'a> FileIoToken<'a> {
+    fn new(boot_services: &'a BootServices, mut buffer: Vec<u8>) -> Result<Self, Error> {
+        let event =
+            boot_services.create_event(EventType::empty(), TPL_CALLBACK, None, ptr::null_mut())?;
+        Ok(FileIoToken {
+            boot_services,
+            raw: Box::new(FileIoTokenRaw {
+                event,
+                status: Status::NULL,
+                buffer_size: buffer.len() as _,
+                buffer: buffer.as_mut_ptr() as _,
+            }),
+            buffer,
+            done: false,
+            result: None,
+        })
+    }
+
+    // if the firmware refused the request the event will never be signaled
+    fn start(&mut self, status: Status) -> Result<(), Error> {
+        self.done = status != Status::NULL;
+        Ok(status.check(())?)
+    }
+
+    fn finish(&mut self) {
+        self.done = true;
+        let mut buffer = mem::take(&mut self.buffer);
+        buffer.truncate(self.raw.buffer_size);
+        self.result = Some(self.raw.status.check(buffer).map_err(Error::from));
+    }
+
+    // the result is given out once, asking again gives `INVALID_PARAMETER`
+    fn take_result(&mut self) -> Result<Vec<u8>, Error> {
+        self.result
+            .take()
+            .unwrap_or_else(|| Err(Status::INVALID_PARAMETER.into()))
+    }
+
+    fn wait_result(&mut self) -> Result<Vec<u8>, Error> {
+        if !self.done {
+            self.boot_services.wait_for_event(&[self.raw.event])?;
+            self.finish();
+        }
+        self.take_result()
+    }
+
+    pub fn event(&self) -> Event {
+        self.raw.event
+    }
+
+    /// Checks the event, the completion is remembered because checking clears the event
+    pub fn is_complete(&mut self) -> Result<bool, Error> {
+        if !self.done && self.boot_services.check_event(self.raw.event)? {
+            self.finish();
+        }
+        Ok(self.done)
+    }
+
+    /// Blocks until the request is done, returns the buffer truncated
+    /// to the count of transferred bytes
+    pub fn wait(mut self) -> Result<Vec<u8>, Error> {
+        self.wait_result()
+    }
+}
+
+/// Polls the event, so the executor is woken up immediately while the request is pending
+impl<'a> Future for FileIoToken<'a> {
+    type Output = Result<Vec<u8>, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match self.is_complete() {
+            Ok(true) => Poll::Ready(self.take_result()),
+            Ok(false) => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            Err(error) => Poll::Ready(Err(error)),
+        }
+    }
+}
+
+impl<'a> Drop for FileIoToken<'a> {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = self.boot_services.wait_for_event(&[self.raw.event]);
+        }
+        let _ = self.boot_services.close_event(self.raw.event);
+    }
+}
+
+/// Request started by `File::open_ex`
+pub struct OpenToken<'a> {
+    token: FileIoToken<'a>,
+    new_handle: Box<*const File>,
+    name: Vec<Char16>,
+}
+
+impl<'a> OpenToken<'a> {
+    pub fn event(&self) -> Event {
+        self.token.event()
+    }
+
+    pub fn is_complete(&mut self) -> Result<bool, Error> {
+        self.token.is_complete()
+    }
+
+    /// Blocks until the file is opened
+    pub fn wait(mut self) -> Result<FileHandle, Error> {
+        self.token.wait_result()?;
+        let new_handle = *self.new_handle;
+        Ok(unsafe { FileHandle::from_raw(new_handle) })
+    }
+}
+
+impl<'a> Future for OpenToken<'a> {
+    type Output = Result<FileHandle, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match Pin::new(&mut self.token).poll(cx) {
+            Poll::Ready(Ok(_)) => {
+                let new_handle = *self.new_handle;
+                Poll::Ready(Ok(unsafe { FileHandle::from_raw(new_handle) }))
+            }
+            Poll::Ready(Err(status)) => Poll::Ready(Err(status)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Waits for the request, a file opened but never taken out of the token is closed
+impl<'a> Drop for OpenToken<'a> {
+    fn drop(&mut self) {
+        // the result is still there only if nobody collected it
+        if self.token.wait_result().is_ok() {
+            drop(unsafe { FileHandle::from_raw(*self.new_handle) });
+        }
     }
 }
@@ -0,0 +1,366 @@
+use common::Guid;
+use common::Word;
+use common::Status;
+use common::Address;
+use common::HasGuid;
+
+use array::Pointer;
+
+use boot_services::BootServices;
+
+use core::mem;
+use core::ptr;
+
+#[repr(C)]
+pub struct GraphicsOutput {
+    query_mode: extern "efiapi" fn(
+        /* in */ this: *const GraphicsOutput,
+        /* in */ mode_number: u32,
+        /* out */ size_of_info: *mut Word,
+        /* out */ info: *mut *mut ModeInfo,
+    ) -> Status,
+    set_mode: extern "efiapi" fn(
+        /* in */ this: *const GraphicsOutput,
+        /* in */ mode_number: u32,
+    ) -> Status,
+    blt: extern "efiapi" fn(
+        /* in */ this: *const GraphicsOutput,
+        /* in out */ blt_buffer: *mut BltPixel,
+        /* in */ blt_operation: u32,
+        /* in */ source_x: Word,
+        /* in */ source_y: Word,
+        /* in */ destination_x: Word,
+        /* in */ destination_y: Word,
+        /* in */ width: Word,
+        /* in */ height: Word,
+        /* in */ delta: Word,
+    ) -> Status,
+
+    mode: Pointer<GraphicsOutputMode>,
+}
+
+#[repr(C)]
+pub struct GraphicsOutputMode {
+    max_mode: u32,
+    mode: u32,
+    info: Pointer<ModeInfo>,
+    size_of_info: Word,
+    frame_buffer_base: Address,
+    frame_buffer_size: Word,
+}
+
+impl GraphicsOutputMode {
+    /// The modes are numbered from zero up to `max_mode` excluded
+    pub fn max_mode(&self) -> u32 {
+        self.max_mode
+    }
+
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    pub fn info(&self) -> &ModeInfo {
+        self.info.as_ref()
+    }
+
+    /// Physical address of the frame buffer, it is not available in `BltOnly` format,
+    /// the frame buffer stays valid after the boot services are exited
+    pub fn frame_buffer_base(&self) -> Address {
+        self.frame_buffer_base
+    }
+
+    /// The size in bytes
+    pub fn frame_buffer_size(&self) -> Word {
+        self.frame_buffer_size
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PixelFormat {
+    /// Byte 0 is red, byte 1 is green, byte 2 is blue, byte 3 is reserved
+    Rgb,
+    /// Byte 0 is blue, byte 1 is green, byte 2 is red, byte 3 is reserved
+    Bgr,
+    /// Described by the `PixelBitmask`
+    Bitmask,
+    /// There is no frame buffer, only `blt` works
+    BltOnly,
+    Unknown(u32),
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PixelBitmask {
+    pub red: u32,
+    pub green: u32,
+    pub blue: u32,
+    pub reserved: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ModeInfo {
+    version: u32,
+    horizontal_resolution: u32,
+    vertical_resolution: u32,
+    pixel_format: u32,
+    pixel_information: PixelBitmask,
+    pixels_per_scan_line: u32,
+}
+
+impl ModeInfo {
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// The width and the height in pixels
+    pub fn resolution(&self) -> (u32, u32) {
+        (self.horizontal_resolution, self.vertical_resolution)
+    }
+
+    pub fn pixel_format(&self) -> PixelFormat {
+        match self.pixel_format {
+            0 => PixelFormat::Rgb,
+            1 => PixelFormat::Bgr,
+            2 => PixelFormat::Bitmask,
+            3 => PixelFormat::BltOnly,
+            format => PixelFormat::Unknown(format),
+        }
+    }
+
+    /// Only meaningful for the `Bitmask` format
+    pub fn pixel_bitmask(&self) -> Option<PixelBitmask> {
+        match self.pixel_format() {
+            PixelFormat::Bitmask => Some(self.pixel_information),
+            _ => None,
+        }
+    }
+
+    /// The stride of the frame buffer, it might be bigger than the width
+    pub fn pixels_per_scan_line(&self) -> u32 {
+        self.pixels_per_scan_line
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq)]
+pub struct BltPixel {
+    pub blue: u8,
+    pub green: u8,
+    pub red: u8,
+    pub reserved: u8,
+}
+
+impl BltPixel {
+    pub fn new(red: u8, green: u8, blue: u8) -> Self {
+        BltPixel {
+            blue,
+            green,
+            red,
+            reserved: 0,
+        }
+    }
+}
+
+/// The coordinates and the sizes are in pixels, `(x, y)` and `(width, height)`,
+/// the `stride` is the count of pixels in a row of the `buffer`
+pub enum BltOperation<'a> {
+    /// Fills the rectangle of the screen with the color
+    VideoFill {
+        color: BltPixel,
+        destination: (Word, Word),
+        size: (Word, Word),
+    },
+    /// Copies the rectangle of the screen into the buffer
+    VideoToBuffer {
+        buffer: &'a mut [BltPixel],
+        stride: Word,
+        source: (Word, Word),
+        destination: (Word, Word),
+        size: (Word, Word),
+    },
+    /// Copies the rectangle of the buffer onto the screen
+    BufferToVideo {
+        buffer: &'a [BltPixel],
+        stride: Word,
+        source: (Word, Word),
+        destination: (Word, Word),
+        size: (Word, Word),
+    },
+    /// Moves the rectangle of the screen
+    VideoToVideo {
+        source: (Word, Word),
+        destination: (Word, Word),
+        size: (Word, Word),
+    },
+}
+
+// the firmware does not know the length of the buffer, returns the stride in bytes,
+// an empty rectangle is rejected as the firmware does
+fn check_buffer(
+    length: usize,
+    stride: Word,
+    (x, y): (Word, Word),
+    (width, height): (Word, Word),
+) -> Result<Word, Status> {
+    let delta = stride.checked_mul(mem::size_of::<BltPixel>());
+    let row_end = x.checked_add(width);
+    // the index right after the last pixel of the rectangle
+    let end = height
+        .checked_sub(1)
+        .and_then(|last_row| last_row.checked_add(y))
+        .and_then(|last_row| last_row.checked_mul(stride))
+        .and_then(|start| start.checked_add(row_end?));
+    match (delta, row_end, end) {
+        (Some(delta), Some(row_end), Some(end)) if row_end <= stride && end <= length => Ok(delta),
+        _ => Err(Status::INVALID_PARAMETER),
+    }
+}
+
+impl GraphicsOutput {
+    /// The information is copied, the firmware buffer is freed
+    pub fn query_mode(
+        &self,
+        boot_services: &BootServices,
+        mode_number: u32,
+    ) -> Result<ModeInfo, Status> {
+        let query_mode = self.query_mode;
+        let mut size_of_info = 0;
+        let mut info = ptr::null_mut();
+        query_mode(self, mode_number, &mut size_of_info, &mut info).check(())?;
+        // newer versions of the structure might be bigger
+        if size_of_info < mem::size_of::<ModeInfo>() {
+            let _ = boot_services.free_pool(info as _);
+            return Err(Status::UNSUPPORTED);
+        }
+        let mode_info = unsafe { ptr::read_unaligned(info) };
+        let _ = boot_services.free_pool(info as _);
+        Ok(mode_info)
+    }
+
+    /// Yields the number and the information of every mode the firmware can query
+    pub fn modes<'a>(&'a self, boot_services: &'a BootServices) -> Modes<'a> {
+        Modes {
+            graphics_output: self,
+            boot_services,
+            mode_number: 0,
+        }
+    }
+
+    /// Clears the screen to black
+    pub fn set_mode(&self, mode_number: u32) -> Result<(), Status> {
+        let set_mode = self.set_mode;
+        set_mode(self, mode_number).check(())
+    }
+
+    pub fn blt(&self, operation: BltOperation) -> Result<(), Status> {
+        let blt = self.blt;
+        match operation {
+            BltOperation::VideoFill {
+                mut color,
+                destination: (x, y),
+                size: (width, height),
+            } => blt(self, &mut color, 0, 0, 0, x, y, width, height, 0),
+            BltOperation::VideoToBuffer {
+                buffer,
+                stride,
+                source: (source_x, source_y),
+                destination,
+                size,
+            } => {
+                let delta = check_buffer(buffer.len(), stride, destination, size)?;
+                let (x, y) = destination;
+                let (width, height) = size;
+                blt(
+                    self,
+                    buffer.as_mut_ptr(),
+                    1,
+                    source_x,
+                    source_y,
+                    x,
+                    y,
+                    width,
+                    height,
+                    delta,
+                )
+            }
+            BltOperation::BufferToVideo {
+                buffer,
+                stride,
+                source,
+                destination: (x, y),
+                size,
+            } => {
+                let delta = check_buffer(buffer.len(), stride, source, size)?;
+                let (source_x, source_y) = source;
+                let (width, height) = size;
+                // the firmware only reads the buffer
+                blt(
+                    self,
+                    buffer.as_ptr() as *mut BltPixel,
+                    2,
+                    source_x,
+                    source_y,
+                    x,
+                    y,
+                    width,
+                    height,
+                    delta,
+                )
+            }
+            BltOperation::VideoToVideo {
+                source: (source_x, source_y),
+                destination: (x, y),
+                size: (width, height),
+            } => blt(
+                self,
+                ptr::null_mut(),
+                3,
+                source_x,
+                source_y,
+                x,
+                y,
+                width,
+                height,
+                0,
+            ),
+        }.check(())
+    }
+
+    pub fn get_mode(&self) -> &GraphicsOutputMode {
+        self.mode.as_ref()
+    }
+}
+
+pub struct Modes<'a> {
+    graphics_output: &'a GraphicsOutput,
+    boot_services: &'a BootServices,
+    mode_number: u32,
+}
+
+impl<'a> Iterator for Modes<'a> {
+    type Item = (u32, ModeInfo);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.mode_number < self.graphics_output.get_mode().max_mode() {
+            let mode_number = self.mode_number;
+            self.mode_number += 1;
+            if let Ok(info) = self
+                .graphics_output
+                .query_mode(self.boot_services, mode_number)
+            {
+                return Some((mode_number, info));
+            }
+        }
+        None
+    }
+}
+
+impl HasGuid for GraphicsOutput {
+    const GUID: Guid = Guid(
+        0x9042a9de,
+        0x23dc,
+        0x4a38,
+        [0x96, 0xfb, 0x7a, 0xde, 0xd0, 0x80, 0x51, 0x6a],
+    );
+}
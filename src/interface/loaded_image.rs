@@ -0,0 +1,115 @@
+use common::Guid;
+use common::Handle;
+use common::Status;
+use common::HasGuid;
+
+use system_table::SystemTable;
+use boot_services::MemoryType;
+
+use super::device_path::DevicePath;
+use super::device_path::node::Ucs2;
+
+use core::slice;
+
+use alloc::string::String;
+
+#[repr(C)]
+pub struct LoadedImage {
+    revision: u32,
+    parent_handle: Handle,
+    system_table: *const SystemTable<'static>,
+
+    device_handle: Handle,
+    file_path: *const u8,
+    _reserved: *const (),
+
+    load_options_size: u32,
+    load_options: *const u8,
+
+    image_base: *const u8,
+    image_size: u64,
+    image_code_type: u32,
+    image_data_type: u32,
+    unload: Option<extern "efiapi" fn(/* in */ image_handle: Handle) -> Status>,
+}
+
+impl LoadedImage {
+    pub fn revision(&self) -> u32 {
+        self.revision
+    }
+
+    /// The image that loaded this one, null for the images loaded by the firmware
+    pub fn parent_handle(&self) -> Handle {
+        self.parent_handle
+    }
+
+    pub fn system_table(&self) -> &SystemTable<'_> {
+        unsafe { &*self.system_table }
+    }
+
+    /// The device the image was loaded from
+    pub fn device_handle(&self) -> Handle {
+        self.device_handle
+    }
+
+    /// The path of the image file relative to the `device_handle`,
+    /// `None` if the image was loaded from a buffer
+    pub fn file_path(&self) -> Option<&DevicePath> {
+        if self.file_path.is_null() {
+            None
+        } else {
+            Some(unsafe { DevicePath::from_ptr(self.file_path) })
+        }
+    }
+
+    /// Raw options passed by the loader
+    pub fn load_options(&self) -> &[u8] {
+        if self.load_options.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.load_options, self.load_options_size as _) }
+        }
+    }
+
+    /// The options decoded as the UCS-2 command line, up to the first null,
+    /// invalid sequences are replaced with `U+FFFD`
+    pub fn command_line(&self) -> String {
+        Ucs2::new(self.load_options()).chars().collect()
+    }
+
+    pub fn image_base(&self) -> *const u8 {
+        self.image_base
+    }
+
+    pub fn image_size(&self) -> u64 {
+        self.image_size
+    }
+
+    /// `None` for the types reserved for OEM and OS loaders
+    pub fn image_code_type(&self) -> Option<MemoryType> {
+        MemoryType::from_raw(self.image_code_type)
+    }
+
+    /// `None` for the types reserved for OEM and OS loaders
+    pub fn image_data_type(&self) -> Option<MemoryType> {
+        MemoryType::from_raw(self.image_data_type)
+    }
+
+    /// Calls the unload function of the image, the `image_handle` is the handle
+    /// this protocol is installed on, fails if the image does not support unloading
+    pub fn unload(&self, image_handle: Handle) -> Result<(), Status> {
+        match self.unload {
+            Some(unload) => unload(image_handle).check(()),
+            None => Err(Status::UNSUPPORTED),
+        }
+    }
+}
+
+impl HasGuid for LoadedImage {
+    const GUID: Guid = Guid(
+        0x5b1b31a1,
+        0x9562,
+        0x11d2,
+        [0x8e, 0x3f, 0x00, 0xa0, 0xc9, 0x69, 0x72, 0x3b],
+    );
+}
@@ -2,3 +2,9 @@ pub mod simple_input;
 pub mod simple_text_output;
 pub mod file;
 pub mod simple_file_system;
+pub mod loaded_image;
+pub mod device_path;
+pub mod device_path_to_text;
+pub mod device_path_from_text;
+pub mod device_path_utilities;
+pub mod graphics_output;
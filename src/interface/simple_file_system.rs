@@ -1,27 +1,37 @@
 use common::Guid;
 use common::Status;
+use common::Error;
 use common::HasGuid;
+use common::Revision;
 
 use super::file::File;
+use super::file::FileHandle;
 
 #[repr(C)]
 pub struct SimpleFileSystem {
     revision: u64,
 
-    open_volume: extern "win64" fn(
+    open_volume: extern "efiapi" fn(
         /* in */ this: *const SimpleFileSystem,
         /* out */ root: *mut *const File,
     ) -> Status,
 }
 
-const REVISION1: u32 = 0x00010000;
+const REVISION1: u64 = 0x00010000;
+
+impl Revision for SimpleFileSystem {
+    fn revision(&self) -> u64 {
+        self.revision
+    }
+}
 
 impl SimpleFileSystem {
-    pub fn open_volume(&self) -> Result<&File, Status> {
-        assert!(self.revision as u32 >= REVISION1);
+    /// Opens the root directory of the volume
+    pub fn open_volume(&self) -> Result<FileHandle, Error> {
+        self.require_revision(REVISION1)?;
         let open_volume = self.open_volume;
         let mut file: *const File = 0 as _;
-        open_volume(self, &mut file).check(unsafe { &*file })
+        Ok(open_volume(self, &mut file).check_map(|| unsafe { FileHandle::from_raw(file) })?)
     }
 }
 
@@ -15,11 +15,11 @@ pub struct InputKey {
 
 #[repr(C)]
 pub struct SimpleInput {
-    reset: extern "win64" fn(
+    reset: extern "efiapi" fn(
         /* in */ this: *const SimpleInput,
         /* in */ extended_verification: Bool,
     ) -> Status,
-    read_key_stroke: extern "win64" fn(
+    read_key_stroke: extern "efiapi" fn(
         /* in */ this: *const SimpleInput,
         /* out */ key: *mut InputKey,
     ) -> Status,
@@ -29,14 +29,14 @@ pub struct SimpleInput {
 
 impl SimpleInput {
     pub fn reset(&self, extended_verification: Bool) -> Result<(), Status> {
-        ((*self).reset)(self, extended_verification).check(())
+        (self.reset)(self, extended_verification).check(())
     }
     pub fn read_key_stroke(&self) -> Result<InputKey, Status> {
         let mut key: InputKey = InputKey {
             scan_code: 0,
             unicode_char: 0,
         };
-        ((*self).read_key_stroke)(self, &mut key).check(key)
+        (self.read_key_stroke)(self, &mut key).check(key)
     }
 
     pub fn get_wait_for_key(&self) -> Event {
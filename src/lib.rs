@@ -1,11 +1,14 @@
 #![no_std]
-#![feature(try_trait)]
 
 #[macro_use]
 extern crate bitflags;
+#[macro_use]
+extern crate alloc;
 
 pub mod common;
 pub mod array;
+pub mod path;
+pub mod pool;
 
 pub mod interface;
 pub mod configuration_table;
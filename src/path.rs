@@ -0,0 +1,331 @@
+use common::Char16;
+use common::Status;
+use common::Error;
+
+use core::fmt;
+use core::ops::Deref;
+use core::str::FromStr;
+use core::borrow::Borrow;
+
+use alloc::borrow::Cow;
+use alloc::borrow::ToOwned;
+use alloc::vec::Vec;
+
+const SEPARATOR: Char16 = b'\\' as _;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PathError {
+    /// Not representable in UCS-2 or not allowed in a file name
+    InvalidCharacter(char),
+}
+
+impl From<PathError> for Status {
+    fn from(_: PathError) -> Self {
+        Status::INVALID_PARAMETER
+    }
+}
+
+impl From<PathError> for Error {
+    fn from(error: PathError) -> Self {
+        Error::Status(error.into())
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PathError::InvalidCharacter(c) => write!(f, "invalid character {:?} in path", c),
+        }
+    }
+}
+
+// control characters and the ones reserved by FAT, the separators are handled by the caller
+fn check_character(c: char) -> Result<Char16, PathError> {
+    match c {
+        '\u{0}'..='\u{1f}' | '"' | '*' | ':' | '<' | '>' | '?' | '|' => {
+            Err(PathError::InvalidCharacter(c))
+        }
+        c if (c as u32) > 0xffff => Err(PathError::InvalidCharacter(c)),
+        c => Ok(c as u32 as Char16),
+    }
+}
+
+/// Borrowed UCS-2 path, components are separated by `\`,
+/// the leading separator means the root of the volume
+#[repr(transparent)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Path {
+    inner: [Char16],
+}
+
+impl Path {
+    fn from_raw(raw: &[Char16]) -> &Self {
+        unsafe { &*(raw as *const [Char16] as *const Path) }
+    }
+
+    /// The `raw` must not contain the terminating null, `/` is not allowed here,
+    /// use `PathBuf::from_str` to normalize it
+    pub fn new(raw: &[Char16]) -> Result<&Self, PathError> {
+        for &c in raw {
+            let c = char::from_u32(c as u32).ok_or(PathError::InvalidCharacter('\u{fffd}'))?;
+            if c == '/' {
+                return Err(PathError::InvalidCharacter(c));
+            }
+            check_character(c)?;
+        }
+        Ok(Path::from_raw(raw))
+    }
+
+    /// The root of the volume
+    pub fn root() -> &'static Self {
+        Path::from_raw(&[SEPARATOR])
+    }
+
+    pub fn as_slice(&self) -> &[Char16] {
+        &self.inner
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn is_absolute(&self) -> bool {
+        self.inner.first() == Some(&SEPARATOR)
+    }
+
+    /// Skips empty components, so `\EFI\\BOOT\` yields `EFI` and `BOOT`
+    pub fn components(&self) -> Components<'_> {
+        Components { rest: &self.inner }
+    }
+
+    /// Returns `None` for the root and for the empty path
+    pub fn parent(&self) -> Option<&Path> {
+        let trimmed = match self.inner.iter().rposition(|&c| c != SEPARATOR) {
+            Some(last) => &self.inner[..=last],
+            None => return None,
+        };
+        let parent = match trimmed.iter().rposition(|&c| c == SEPARATOR) {
+            Some(position) => &trimmed[..position],
+            None => return Some(Path::from_raw(&[])),
+        };
+        // `a\\b` gives `a`, and `\\a` gives the root
+        match parent.iter().rposition(|&c| c != SEPARATOR) {
+            Some(last) => Some(Path::from_raw(&parent[..=last])),
+            None => Some(Path::from_raw(&self.inner[..1])),
+        }
+    }
+
+    /// If `path` is absolute it replaces `self`
+    pub fn join<P>(&self, path: P) -> PathBuf
+    where
+        P: AsRef<Path>,
+    {
+        let mut buf = self.to_owned();
+        buf.push(path);
+        buf
+    }
+
+    /// The null terminated string for the firmware
+    pub fn to_ucs2_with_nul(&self) -> Vec<Char16> {
+        let mut raw = Vec::with_capacity(self.inner.len() + 1);
+        raw.extend_from_slice(&self.inner);
+        raw.push(0);
+        raw
+    }
+}
+
+impl AsRef<Path> for Path {
+    fn as_ref(&self) -> &Path {
+        self
+    }
+}
+
+impl ToOwned for Path {
+    type Owned = PathBuf;
+
+    fn to_owned(&self) -> Self::Owned {
+        PathBuf {
+            inner: self.inner.to_vec(),
+        }
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use core::fmt::Write;
+
+        for &c in &self.inner {
+            f.write_char(char::from_u32(c as u32).unwrap_or('\u{fffd}'))?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Components<'a> {
+    rest: &'a [Char16],
+}
+
+impl<'a> Iterator for Components<'a> {
+    type Item = &'a Path;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.rest.is_empty() {
+            let end = self
+                .rest
+                .iter()
+                .position(|&c| c == SEPARATOR)
+                .unwrap_or(self.rest.len());
+            let (component, rest) = self.rest.split_at(end);
+            self.rest = if rest.is_empty() { rest } else { &rest[1..] };
+            if !component.is_empty() {
+                return Some(Path::from_raw(component));
+            }
+        }
+        None
+    }
+}
+
+/// Owned UCS-2 path
+#[derive(Clone, Default, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct PathBuf {
+    inner: Vec<Char16>,
+}
+
+impl PathBuf {
+    pub fn new() -> Self {
+        PathBuf::default()
+    }
+
+    pub fn as_path(&self) -> &Path {
+        Path::from_raw(&self.inner)
+    }
+
+    /// If `path` is absolute it replaces `self`
+    pub fn push<P>(&mut self, path: P)
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        if path.is_absolute() {
+            self.inner.clear();
+        } else if !self.inner.is_empty() && self.inner.last() != Some(&SEPARATOR) {
+            self.inner.push(SEPARATOR);
+        }
+        self.inner.extend_from_slice(&path.inner);
+    }
+
+    /// Truncates `self` to its parent, returns `false` if there is no parent
+    pub fn pop(&mut self) -> bool {
+        match self.parent().map(|parent| parent.inner.len()) {
+            Some(length) => {
+                self.inner.truncate(length);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn into_inner(self) -> Vec<Char16> {
+        self.inner
+    }
+}
+
+impl Deref for PathBuf {
+    type Target = Path;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_path()
+    }
+}
+
+impl Borrow<Path> for PathBuf {
+    fn borrow(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl AsRef<Path> for PathBuf {
+    fn as_ref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl<'a> From<&'a Path> for PathBuf {
+    fn from(path: &'a Path) -> Self {
+        path.to_owned()
+    }
+}
+
+/// Accepts both `/` and `\` as separators
+impl FromStr for PathBuf {
+    type Err = PathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.chars()
+            .map(|c| match c {
+                '/' | '\\' => Ok(SEPARATOR),
+                c => check_character(c),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|inner| PathBuf { inner })
+    }
+}
+
+impl fmt::Display for PathBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_path().fmt(f)
+    }
+}
+
+/// Anything that is a path or can be parsed into a path
+pub trait ToPath {
+    fn to_path(&self) -> Result<Cow<'_, Path>, PathError>;
+}
+
+impl ToPath for Path {
+    fn to_path(&self) -> Result<Cow<'_, Path>, PathError> {
+        Ok(Cow::Borrowed(self))
+    }
+}
+
+impl ToPath for PathBuf {
+    fn to_path(&self) -> Result<Cow<'_, Path>, PathError> {
+        Ok(Cow::Borrowed(self.as_path()))
+    }
+}
+
+impl ToPath for str {
+    fn to_path(&self) -> Result<Cow<'_, Path>, PathError> {
+        self.parse().map(Cow::Owned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PathBuf;
+
+    use alloc::string::String;
+    use alloc::string::ToString;
+
+    fn parent(path: &str) -> Option<String> {
+        let path = path.parse::<PathBuf>().unwrap();
+        path.parent().map(|parent| parent.to_string())
+    }
+
+    #[test]
+    fn parent_of_single_separators() {
+        assert_eq!(parent("\\EFI\\BOOT\\"), Some("\\EFI".to_string()));
+        assert_eq!(parent("\\EFI"), Some("\\".to_string()));
+        assert_eq!(parent("EFI"), Some("".to_string()));
+        assert_eq!(parent("\\"), None);
+        assert_eq!(parent(""), None);
+    }
+
+    #[test]
+    fn parent_of_repeated_separators() {
+        assert_eq!(parent("a\\\\b"), Some("a".to_string()));
+        assert_eq!(parent("\\EFI\\\\BOOT\\\\"), Some("\\EFI".to_string()));
+        assert_eq!(parent("\\\\EFI"), Some("\\".to_string()));
+        assert_eq!(parent("\\\\"), None);
+    }
+}
@@ -0,0 +1,157 @@
+use common::Word;
+use common::Status;
+
+use boot_services::BootServices;
+use boot_services::MemoryType;
+
+use core::ops::Deref;
+use core::ops::DerefMut;
+use core::mem;
+use core::ptr;
+use core::slice;
+
+/// Value allocated from the pool, it is freed on drop,
+/// the unsized values come from the firmware, e.g. the device paths
+pub struct PoolBox<'a, T>
+where
+    T: ?Sized,
+{
+    boot_services: &'a BootServices,
+    raw: *mut T,
+}
+
+impl<'a, T> PoolBox<'a, T>
+where
+    T: Sized,
+{
+    /// Allocates `LoaderData` memory and moves the `value` there
+    pub fn new(boot_services: &'a BootServices, value: T) -> Result<Self, Status> {
+        let raw = boot_services.allocate_pool(MemoryType::LoaderData, mem::size_of::<T>())?;
+        let raw = raw as *mut T;
+        unsafe { ptr::write(raw, value) };
+        Ok(PoolBox { boot_services, raw })
+    }
+}
+
+impl<'a, T> PoolBox<'a, T>
+where
+    T: ?Sized,
+{
+    /// # Safety
+    ///
+    /// The `raw` must be a valid value allocated from the pool and not owned by anything else
+    pub unsafe fn from_raw(boot_services: &'a BootServices, raw: *mut T) -> Self {
+        PoolBox { boot_services, raw }
+    }
+
+    /// The caller is responsible for freeing the memory
+    pub fn into_raw(self) -> *mut T {
+        let raw = self.raw;
+        mem::forget(self);
+        raw
+    }
+}
+
+impl<'a, T> Deref for PoolBox<'a, T>
+where
+    T: ?Sized,
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.raw }
+    }
+}
+
+impl<'a, T> DerefMut for PoolBox<'a, T>
+where
+    T: ?Sized,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.raw }
+    }
+}
+
+impl<'a, T> Drop for PoolBox<'a, T>
+where
+    T: ?Sized,
+{
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(self.raw) };
+        let _ = self.boot_services.free_pool(self.raw as _);
+    }
+}
+
+/// Array allocated from the pool, usually by the firmware on behalf of the caller,
+/// it is freed on drop, the elements are not dropped
+pub struct PoolSlice<'a, T>
+where
+    T: Sized,
+{
+    boot_services: &'a BootServices,
+    raw: *mut T,
+    length: Word,
+}
+
+impl<'a, T> PoolSlice<'a, T>
+where
+    T: Sized,
+{
+    /// # Safety
+    ///
+    /// The `raw` must point to `length` valid elements allocated from the pool
+    /// and not owned by anything else, it might be null if the `length` is zero
+    pub unsafe fn from_raw(boot_services: &'a BootServices, raw: *mut T, length: Word) -> Self {
+        PoolSlice {
+            boot_services,
+            raw,
+            length,
+        }
+    }
+
+    /// The caller is responsible for freeing the memory
+    pub fn into_raw(self) -> (*mut T, Word) {
+        let raw = (self.raw, self.length);
+        mem::forget(self);
+        raw
+    }
+}
+
+impl<'a, T> Deref for PoolSlice<'a, T>
+where
+    T: Sized,
+{
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        if self.raw.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.raw, self.length) }
+        }
+    }
+}
+
+impl<'a, T> DerefMut for PoolSlice<'a, T>
+where
+    T: Sized,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if self.raw.is_null() {
+            &mut []
+        } else {
+            unsafe { slice::from_raw_parts_mut(self.raw, self.length) }
+        }
+    }
+}
+
+impl<'a, T> Drop for PoolSlice<'a, T>
+where
+    T: Sized,
+{
+    fn drop(&mut self) {
+        if !self.raw.is_null() {
+            let _ = self.boot_services.free_pool(self.raw as _);
+        }
+    }
+}